@@ -0,0 +1,442 @@
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Error, Result};
+
+#[derive(Debug, Clone)]
+pub enum Opcode {
+    /// The adv instruction (opcode 0) performs division.
+    /// The numerator is the value in the A register.
+    /// The denominator is found by raising 2 to the power of the instruction's combo operand.
+    /// (So, an operand of 2 would divide A by 4 (2^2); an operand of 5 would divide A by 2^B.)
+    /// The result of the division operation is truncated to an integer and then written to the A register.
+    Adv(ComboOperand),
+
+    /// The bxl instruction (opcode 1) calculates the bitwise XOR of register B
+    /// and the instruction's literal operand, then stores the result in register B.
+    Bxl(isize),
+
+    /// The bst instruction (opcode 2) calculates the value of its combo operand modulo 8
+    /// (thereby keeping only its lowest 3 bits), then writes that value to the B register.
+    /// For other word widths, the modulus is `2^word_bits`.
+    Bst(ComboOperand),
+
+    /// The jnz instruction (opcode 3) does nothing if the A register is 0.
+    /// However, if the A register is not zero, it jumps by setting the instruction pointer
+    /// to the value of its literal operand; if this instruction jumps, the instruction pointer
+    /// is not increased by 2 after this instruction.
+    Jnz(usize),
+
+    /// The bxc instruction (opcode 4) calculates the bitwise XOR of register B and register C,
+    /// then stores the result in register B. (For legacy reasons, this instruction reads
+    /// an operand but ignores it.)
+    Bxc(isize),
+
+    /// The out instruction (opcode 5) calculates the value of its combo operand modulo 8,
+    /// then outputs that value. (If a program outputs multiple values, they are separated by commas.)
+    /// For other word widths, the modulus is `2^word_bits`.
+    Out(ComboOperand),
+
+    /// The bdv instruction (opcode 6) works exactly like the adv instruction
+    /// except that the result is stored in the B register. (The numerator is still read from the A register.)
+    Bdv(ComboOperand),
+
+    /// The cdv instruction (opcode 7) works exactly like the adv instruction
+    /// except that the result is stored in the C register. (The numerator is still read from the A register.)
+    Cdv(ComboOperand),
+}
+
+impl Opcode {
+    pub fn from_tape(opcode: u8, operand: u8) -> Result<Self> {
+        let x = match opcode {
+            0 => Opcode::Adv(ComboOperand::from_tape(operand)),
+            1 => Opcode::Bxl(operand as isize),
+            2 => Opcode::Bst(ComboOperand::from_tape(operand)),
+            3 => Opcode::Jnz(operand as usize),
+            4 => Opcode::Bxc(operand as isize),
+            5 => Opcode::Out(ComboOperand::from_tape(operand)),
+            6 => Opcode::Bdv(ComboOperand::from_tape(operand)),
+            7 => Opcode::Cdv(ComboOperand::from_tape(operand)),
+            _ => {
+                bail!("Bad opcode: {}", opcode);
+            }
+        };
+
+        Ok(x)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum ComboOperand {
+    Literal(isize),
+    Register(char),
+}
+
+impl ComboOperand {
+    pub fn from_tape(operand: u8) -> Self {
+        match operand {
+            4 => ComboOperand::Register('A'),
+            5 => ComboOperand::Register('B'),
+            6 => ComboOperand::Register('C'),
+            _ => ComboOperand::Literal(operand as isize),
+        }
+    }
+
+    pub fn get_value(&self, registers: &HashMap<char, isize>) -> isize {
+        match self {
+            ComboOperand::Literal(v) => *v,
+            ComboOperand::Register(r) => *registers.get(r).unwrap_or(&0),
+        }
+    }
+}
+
+/// The chronospatial computer from 2024 day 17
+#[derive(Debug, Clone)]
+pub struct Bitcode {
+    pub regs: HashMap<char, isize>,
+
+    pub ip: usize,
+    pub tape: Vec<u8>,
+
+    /// Width of the machine words in bits. Outputs and `bst` results are taken modulo
+    /// `2^word_bits`. The original puzzle uses a 3-bit computer.
+    word_bits: u32,
+}
+
+impl std::str::FromStr for Bitcode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (registers, program) = s
+            .split_once("\n\n")
+            .ok_or(anyhow!("Expect delimiter line"))?;
+
+        let mut regs = HashMap::new();
+        for line in registers.lines() {
+            // lines look like `Register A: 729`
+            let Some((name, val)) = line
+                .trim()
+                .strip_prefix("Register ")
+                .and_then(|l| l.split_once(':'))
+            else {
+                continue;
+            };
+
+            let mut chars = name.chars();
+            let (Some(reg), None) = (chars.next(), chars.next()) else {
+                bail!("Bad register name: '{}'", name);
+            };
+
+            let val: isize = val.trim().parse().context("Parse register value")?;
+
+            regs.insert(reg, val);
+        }
+
+        let mut tape = Vec::new();
+        for line in program.strip_prefix("Program: ").unwrap_or(program).lines() {
+            tape.extend(
+                line.split(',')
+                    .map(|v| v.trim().parse::<u8>().context("Parse tape"))
+                    .collect::<Result<Vec<_>>>()?,
+            );
+        }
+
+        Ok(Self::new(regs, tape))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepResult {
+    Terminated,
+    Working,
+    Output(isize),
+}
+
+impl Bitcode {
+    /// Create a 3-bit computer with the given registers and program
+    pub fn new(regs: HashMap<char, isize>, tape: Vec<u8>) -> Self {
+        Self {
+            regs,
+            ip: 0,
+            tape,
+            word_bits: 3,
+        }
+    }
+
+    /// Change the width of machine words, which must be between 1 and 62 bits
+    pub fn with_word_bits(mut self, word_bits: u32) -> Self {
+        assert!(
+            (1..=62).contains(&word_bits),
+            "Word width must be between 1 and 62 bits"
+        );
+        self.word_bits = word_bits;
+        self
+    }
+
+    /// Width of the machine words in bits
+    pub fn word_bits(&self) -> u32 {
+        self.word_bits
+    }
+
+    /// Take a value modulo the word size, like the puzzle's "modulo 8" for 3-bit words
+    fn to_word(&self, val: isize) -> isize {
+        val % (1 << self.word_bits)
+    }
+
+    pub fn get_reg(&self, register: char) -> isize {
+        *self.regs.get(&register).unwrap_or(&0)
+    }
+
+    /// Execute a single instruction
+    pub fn step(&mut self) -> Result<StepResult> {
+        let Some(&opcode) = self.tape.get(self.ip) else {
+            return Ok(StepResult::Terminated);
+        };
+
+        let Some(&operand) = self.tape.get(self.ip + 1) else {
+            return Ok(StepResult::Terminated);
+        };
+
+        let opcode = Opcode::from_tape(opcode, operand)?;
+
+        match opcode {
+            Opcode::Adv(op) => {
+                // The adv instruction (opcode 0) performs division.
+
+                // The numerator is the value in the A register.
+                let num = self.get_reg('A');
+
+                // The denominator is found by raising 2 to the power of the instruction's combo operand.
+                let shift = op.get_value(&self.regs);
+
+                // (So, an operand of 2 would divide A by 4 (2^2); an operand of 5 would divide A by 2^B.)
+                let val = num >> shift;
+
+                // The result of the division operation is truncated to an integer and then written to the A register.
+                self.regs.insert('A', val);
+            }
+            Opcode::Bxl(op) => {
+                // The bxl instruction (opcode 1) calculates the bitwise XOR of register B
+                // and the instruction's literal operand, then stores the result in register B.
+
+                let num = self.get_reg('B');
+                let val = num ^ op;
+                self.regs.insert('B', val);
+            }
+            Opcode::Bst(op) => {
+                // The bst instruction (opcode 2) calculates the value of its combo operand modulo
+                // the word size (8 for 3-bit words, thereby keeping only its lowest 3 bits), then
+                // writes that value to the B register.
+
+                let val = self.to_word(op.get_value(&self.regs));
+                self.regs.insert('B', val);
+            }
+            Opcode::Jnz(op) => {
+                // The jnz instruction (opcode 3) does nothing if the A register is 0.
+
+                let num = self.get_reg('A');
+
+                if num != 0 {
+                    // However, if the A register is not zero, it jumps by setting the instruction pointer
+                    // to the value of its literal operand; if this instruction jumps, the instruction pointer
+                    // is not increased by 2 after this instruction.
+
+                    self.ip = op;
+                    return Ok(StepResult::Working);
+                }
+            }
+            Opcode::Bxc(_op) => {
+                // The bxc instruction (opcode 4) calculates the bitwise XOR of register B and register C,
+                // then stores the result in register B. (For legacy reasons, this instruction reads
+                // an operand but ignores it.)
+
+                let b = self.get_reg('B');
+                let c = self.get_reg('C');
+
+                let val = b ^ c;
+                self.regs.insert('B', val);
+            }
+            Opcode::Out(op) => {
+                // The out instruction (opcode 5) calculates the value of its combo operand modulo
+                // the word size (8 for 3-bit words), then outputs that value. (If a program outputs
+                // multiple values, they are separated by commas.)
+
+                let val = self.to_word(op.get_value(&self.regs));
+                self.ip += 2;
+
+                return Ok(StepResult::Output(val));
+            }
+            Opcode::Bdv(op) => {
+                // The bdv instruction (opcode 6) works exactly like the adv instruction
+                // except that the result is stored in the B register. (The numerator is still read from the A register.)
+
+                // The numerator is the value in the A register.
+                let num = self.get_reg('A');
+
+                // The denominator is found by raising 2 to the power of the instruction's combo operand.
+                let shift = op.get_value(&self.regs);
+
+                // (So, an operand of 2 would divide A by 4 (2^2); an operand of 5 would divide A by 2^B.)
+                let val = num >> shift;
+
+                // The result of the division operation is truncated to an integer and then written to the B register.
+                self.regs.insert('B', val);
+            }
+            Opcode::Cdv(op) => {
+                // The cdv instruction (opcode 7) works exactly like the adv instruction
+                // except that the result is stored in the C register. (The numerator is still read from the A register.)
+
+                // The numerator is the value in the A register.
+                let num = self.get_reg('A');
+
+                // The denominator is found by raising 2 to the power of the instruction's combo operand.
+                let shift = op.get_value(&self.regs);
+
+                // (So, an operand of 2 would divide A by 4 (2^2); an operand of 5 would divide A by 2^B.)
+                let val = num >> shift;
+
+                // The result of the division operation is truncated to an integer and then written to the C register.
+                self.regs.insert('C', val);
+            }
+        }
+
+        self.ip += 2;
+        Ok(StepResult::Working)
+    }
+
+    /// Run the program until it terminates, returning all outputs
+    pub fn run(&mut self) -> Result<Vec<isize>> {
+        let mut outputs = Vec::new();
+
+        loop {
+            match self.step()? {
+                StepResult::Terminated => break,
+                StepResult::Working => {}
+                StepResult::Output(v) => outputs.push(v),
+            }
+        }
+
+        Ok(outputs)
+    }
+
+    /// Run a copy of the program from the start with a different value for register A
+    pub fn run_with_a(&self, a: isize) -> Result<Vec<isize>> {
+        let mut candidate = self.clone();
+        candidate.regs.insert('A', a);
+        candidate.run()
+    }
+
+    /// Find the lowest value for register A that makes the program output itself.
+    ///
+    /// This assumes the common program structure of consuming one machine word of A per
+    /// output, see `find_quine_with`.
+    pub fn find_quine(&self) -> Result<isize> {
+        self.find_quine_with(self.word_bits)
+    }
+
+    /// Find the lowest value for register A that makes the program output itself, assuming
+    /// that every loop of the program shifts A right by `shift` bits and outputs a value.
+    ///
+    /// The search reconstructs A from its most significant bits onwards, extending every
+    /// candidate that reproduces the tail of the program by `shift` more bits.
+    pub fn find_quine_with(&self, shift: u32) -> Result<isize> {
+        let mut valid = vec![0];
+
+        for len in 1..=self.tape.len() {
+            let mut new_valid = Vec::new();
+            for n in valid {
+                for i in 0..(1 << shift) {
+                    let m = (n << shift) + i;
+                    let output = self.run_with_a(m)?;
+                    let expected = self.tape[self.tape.len() - len..]
+                        .iter()
+                        .map(|v| *v as isize);
+                    if output.into_iter().eq(expected) {
+                        new_valid.push(m);
+                    }
+                }
+            }
+
+            valid = new_valid;
+        }
+
+        valid.into_iter().min().ok_or(anyhow!("No solution found"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_example_program() {
+        let mut prog: Bitcode =
+            "Register A: 729\nRegister B: 0\nRegister C: 0\n\nProgram: 0,1,5,4,3,0\n"
+                .parse()
+                .unwrap();
+
+        assert_eq!(prog.run().unwrap(), vec![4, 6, 3, 5, 6, 3, 5, 2, 1, 0]);
+    }
+
+    #[test]
+    fn test_instructions() {
+        let mut regs = HashMap::new();
+        regs.insert('C', 9);
+        let mut prog = Bitcode::new(regs, vec![2, 6]);
+        prog.run().unwrap();
+        assert_eq!(prog.get_reg('B'), 1);
+
+        let prog = Bitcode::new(HashMap::new(), vec![5, 0, 5, 1, 5, 4]);
+        assert_eq!(prog.run_with_a(10).unwrap(), vec![0, 1, 2]);
+
+        let mut regs = HashMap::new();
+        regs.insert('A', 2024);
+        let mut prog = Bitcode::new(regs, vec![0, 1, 5, 4, 3, 0]);
+        assert_eq!(prog.run().unwrap(), vec![4, 2, 5, 6, 7, 7, 7, 7, 3, 1, 0]);
+        assert_eq!(prog.get_reg('A'), 0);
+
+        let mut regs = HashMap::new();
+        regs.insert('B', 2024);
+        regs.insert('C', 43690);
+        let mut prog = Bitcode::new(regs, vec![4, 0]);
+        prog.run().unwrap();
+        assert_eq!(prog.get_reg('B'), 44354);
+    }
+
+    #[test]
+    fn test_find_quine() {
+        let prog: Bitcode =
+            "Register A: 2024\nRegister B: 0\nRegister C: 0\n\nProgram: 0,3,5,4,3,0\n"
+                .parse()
+                .unwrap();
+
+        let a = prog.find_quine().unwrap();
+        assert_eq!(a, 117440);
+        let tape: Vec<isize> = prog.tape.iter().map(|v| *v as isize).collect();
+        assert_eq!(prog.run_with_a(a).unwrap(), tape);
+    }
+
+    #[test]
+    fn test_word_bits() {
+        // with 2-bit words, outputs are truncated to 0..4
+        let prog = Bitcode::new(HashMap::new(), vec![5, 4]).with_word_bits(2);
+        assert_eq!(prog.run_with_a(7).unwrap(), vec![3]);
+
+        // wider words keep outputs above 255 intact
+        let prog = Bitcode::new(HashMap::new(), vec![5, 4]).with_word_bits(16);
+        assert_eq!(prog.run_with_a(0x12345).unwrap(), vec![0x2345]);
+        assert_eq!(prog.word_bits(), 16);
+
+        let prog = Bitcode::new(HashMap::new(), vec![5, 4]).with_word_bits(62);
+        assert_eq!(prog.run_with_a(1 << 61).unwrap(), vec![1 << 61]);
+
+        // negative values behave like the puzzle's remainder
+        let prog = Bitcode::new(HashMap::new(), vec![5, 4]);
+        assert_eq!(prog.run_with_a(-9).unwrap(), vec![-1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Word width must be between 1 and 62 bits")]
+    fn test_word_bits_too_wide() {
+        Bitcode::new(HashMap::new(), vec![]).with_word_bits(63);
+    }
+}
@@ -1,4 +1,7 @@
 use strum::EnumIter;
+use thiserror::Error;
+
+use crate::map::{checked_offset, IntCoord};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, EnumIter, PartialOrd, Ord)]
 pub enum Direction {
@@ -29,6 +32,24 @@ impl Direction {
         }
     }
 
+    /// Get the direction of a unit step `[di, dj]`, or `None` for diagonal, zero or longer steps
+    pub fn from_delta(delta: [i32; 2]) -> Option<Self> {
+        match delta {
+            [-1, 0] => Some(Direction::North),
+            [0, 1] => Some(Direction::East),
+            [1, 0] => Some(Direction::South),
+            [0, -1] => Some(Direction::West),
+            _ => None,
+        }
+    }
+
+    /// Step a 2D coordinate one tile into this direction, or `None` if it would leave the
+    /// range of `I` (e.g. going North from row 0 of an unsigned map)
+    pub fn try_step<I: IntCoord>(&self, pos: [I; 2]) -> Option<[I; 2]> {
+        let [di, dj] = self.dpos();
+        checked_offset(pos, [di as i64, dj as i64])
+    }
+
     pub fn rot_left(&self) -> Self {
         match self {
             Direction::North => Direction::West,
@@ -47,6 +68,17 @@ impl Direction {
         }
     }
 
+    /// Rotate by a number of quarter turns, clockwise for positive and counter-clockwise for
+    /// negative numbers
+    pub fn turn(&self, quarter_turns: i32) -> Self {
+        match quarter_turns.rem_euclid(4) {
+            0 => *self,
+            1 => self.rot_right(),
+            2 => self.reverse(),
+            _ => self.rot_left(),
+        }
+    }
+
     pub fn reverse(&self) -> Self {
         match self {
             Direction::North => Direction::South,
@@ -56,6 +88,15 @@ impl Direction {
         }
     }
 
+    /// Get the two directions at a right angle to this one, in the order left, right
+    pub fn perpendicular(&self) -> [Direction; 2] {
+        [self.rot_left(), self.rot_right()]
+    }
+
+    pub fn is_perpendicular_to(&self, other: &Direction) -> bool {
+        self.perpendicular().contains(other)
+    }
+
     pub fn to_num(&self) -> i32 {
         match self {
             Direction::North => 3,
@@ -65,3 +106,199 @@ impl Direction {
         }
     }
 }
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TurnWalkError {
+    #[error("Bad path character: '{}'", .0)]
+    BadChar(char),
+
+    #[error("Step count too large at character {}", .0)]
+    TooManySteps(usize),
+}
+
+/// A single instruction of a turn-and-walk path such as `10R5L3`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TurnWalk {
+    Walk(usize),
+    Left,
+    Right,
+}
+
+impl TurnWalk {
+    /// Parse a path of step counts and `L`/`R` turns, ignoring whitespace
+    pub fn parse(s: &str) -> Result<Vec<TurnWalk>, TurnWalkError> {
+        let mut out = Vec::new();
+        let mut steps: Option<usize> = None;
+
+        for (i, c) in s.chars().enumerate() {
+            if let Some(d) = c.to_digit(10) {
+                let n = steps
+                    .unwrap_or(0)
+                    .checked_mul(10)
+                    .and_then(|n| n.checked_add(d as usize))
+                    .ok_or(TurnWalkError::TooManySteps(i))?;
+                steps = Some(n);
+                continue;
+            }
+
+            if let Some(n) = steps.take() {
+                out.push(TurnWalk::Walk(n));
+            }
+
+            match c {
+                'L' => out.push(TurnWalk::Left),
+                'R' => out.push(TurnWalk::Right),
+                c if c.is_whitespace() => {}
+                _ => return Err(TurnWalkError::BadChar(c)),
+            }
+        }
+
+        if let Some(n) = steps {
+            out.push(TurnWalk::Walk(n));
+        }
+
+        Ok(out)
+    }
+
+    /// Apply the instruction to a position and heading.
+    ///
+    /// For walks, `step` is called once per step and returns the next position and heading
+    /// (allowing for wrap-around rules that change the heading), or `None` if the way is
+    /// blocked, which ends the walk early.
+    pub fn apply<P, F>(&self, pos: P, dir: Direction, mut step: F) -> (P, Direction)
+    where
+        F: FnMut(&P, Direction) -> Option<(P, Direction)>,
+    {
+        match self {
+            TurnWalk::Left => (pos, dir.rot_left()),
+            TurnWalk::Right => (pos, dir.rot_right()),
+            TurnWalk::Walk(n) => {
+                let (mut pos, mut dir) = (pos, dir);
+                for _ in 0..*n {
+                    match step(&pos, dir) {
+                        Some((p, d)) => {
+                            pos = p;
+                            dir = d;
+                        }
+                        None => break,
+                    }
+                }
+                (pos, dir)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn test_perpendicular() {
+        assert_eq!(
+            Direction::North.perpendicular(),
+            [Direction::West, Direction::East]
+        );
+
+        assert!(Direction::North.is_perpendicular_to(&Direction::East));
+        assert!(!Direction::North.is_perpendicular_to(&Direction::South));
+        assert!(!Direction::North.is_perpendicular_to(&Direction::North));
+    }
+
+    #[test]
+    fn test_turn_walk_parse() {
+        assert_eq!(
+            TurnWalk::parse("10R5L3").unwrap(),
+            vec![
+                TurnWalk::Walk(10),
+                TurnWalk::Right,
+                TurnWalk::Walk(5),
+                TurnWalk::Left,
+                TurnWalk::Walk(3),
+            ]
+        );
+
+        assert_eq!(TurnWalk::parse("").unwrap(), vec![]);
+        assert_eq!(
+            TurnWalk::parse("10X5").unwrap_err(),
+            TurnWalkError::BadChar('X')
+        );
+        assert_eq!(
+            TurnWalkError::BadChar('X').to_string(),
+            "Bad path character: 'X'"
+        );
+
+        // step counts that do not fit into a usize are rejected
+        let path = format!("L{}0", usize::MAX);
+        assert_eq!(
+            TurnWalk::parse(&path).unwrap_err(),
+            TurnWalkError::TooManySteps(path.len() - 1)
+        );
+        assert_eq!(
+            TurnWalk::parse(&format!("{}R", usize::MAX)).unwrap(),
+            vec![TurnWalk::Walk(usize::MAX), TurnWalk::Right]
+        );
+    }
+
+    #[test]
+    fn test_turn_walk_apply() {
+        // walk on a line of cells 0..=4 heading east
+        let step = |p: &i32, d: Direction| {
+            let next = p + d.dpos()[1];
+            if (0..=4).contains(&next) {
+                Some((next, d))
+            } else {
+                None
+            }
+        };
+
+        let state = TurnWalk::Walk(3).apply(0, Direction::East, step);
+        assert_eq!(state, (3, Direction::East));
+
+        let state = TurnWalk::Walk(3).apply(state.0, state.1, step);
+        assert_eq!(state, (4, Direction::East));
+
+        let state = TurnWalk::Right.apply(state.0, state.1, step);
+        assert_eq!(state, (4, Direction::South));
+    }
+
+    #[test]
+    fn test_from_delta() {
+        assert_eq!(Direction::from_delta([-1, 0]), Some(Direction::North));
+        assert_eq!(Direction::from_delta([0, 1]), Some(Direction::East));
+        assert_eq!(Direction::from_delta([1, 0]), Some(Direction::South));
+        assert_eq!(Direction::from_delta([0, -1]), Some(Direction::West));
+
+        for d in Direction::iter() {
+            assert_eq!(Direction::from_delta(d.dpos()), Some(d));
+        }
+
+        assert_eq!(Direction::from_delta([2, 0]), None);
+        assert_eq!(Direction::from_delta([1, 1]), None);
+        assert_eq!(Direction::from_delta([0, 0]), None);
+    }
+
+    #[test]
+    fn test_try_step() {
+        assert_eq!(Direction::North.try_step([0usize, 3]), None);
+        assert_eq!(Direction::West.try_step([2usize, 0]), None);
+        assert_eq!(Direction::South.try_step([0usize, 3]), Some([1, 3]));
+        assert_eq!(Direction::East.try_step([0usize, 3]), Some([0, 4]));
+
+        assert_eq!(Direction::North.try_step([0i32, 0]), Some([-1, 0]));
+        assert_eq!(Direction::East.try_step([0, i64::MAX]), None);
+    }
+
+    #[test]
+    fn test_turn() {
+        for d in Direction::iter() {
+            assert_eq!(d.turn(0), d);
+            assert_eq!(d.turn(1), d.rot_right());
+            assert_eq!(d.turn(-1), d.rot_left());
+            assert_eq!(d.turn(2), d.reverse());
+            assert_eq!(d.turn(-2), d.reverse());
+            assert_eq!(d.turn(7), d.rot_left());
+        }
+    }
+}
@@ -0,0 +1,73 @@
+use std::{collections::HashMap, fmt::Write, hash::Hash};
+
+/// Render a directed graph in GraphViz DOT format.
+///
+/// Nodes are given ids `n0`, `n1`, ... in order of first appearance and are labelled using
+/// `label`. Nodes that only appear in `edges` are added automatically.
+pub fn to_dot<N, L>(
+    nodes: impl IntoIterator<Item = N>,
+    edges: impl IntoIterator<Item = (N, N)>,
+    label: L,
+) -> String
+where
+    N: Hash + Eq + Clone,
+    L: Fn(&N) -> String,
+{
+    let mut ids: HashMap<N, usize> = HashMap::new();
+    let mut out = String::from("digraph {\n");
+
+    let mut node_id = |n: &N, out: &mut String| -> usize {
+        if let Some(&id) = ids.get(n) {
+            return id;
+        }
+
+        let id = ids.len();
+        ids.insert(n.clone(), id);
+        writeln!(out, "  n{} [label=\"{}\"];", id, escape(&label(n))).unwrap();
+        id
+    };
+
+    for n in nodes {
+        node_id(&n, &mut out);
+    }
+
+    for (a, b) in edges {
+        let a = node_id(&a, &mut out);
+        let b = node_id(&b, &mut out);
+        writeln!(out, "  n{} -> n{};", a, b).unwrap();
+    }
+
+    out.push_str("}\n");
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_dot() {
+        let dot = to_dot(vec!["a", "b"], vec![("a", "b")], |n| n.to_string());
+
+        assert_eq!(
+            dot,
+            "digraph {\n  n0 [label=\"a\"];\n  n1 [label=\"b\"];\n  n0 -> n1;\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dot_implicit_nodes() {
+        let dot = to_dot(vec![[0, 0]], vec![([0, 0], [0, 1])], |n| format!("{:?}", n));
+
+        assert!(dot.contains("n0 [label=\"[0, 0]\"];"));
+        assert!(dot.contains("n1 [label=\"[0, 1]\"];"));
+        assert!(dot.contains("n0 -> n1;"));
+
+        let quoted = to_dot(vec!["say \"hi\""], vec![], |n| n.to_string());
+        assert!(quoted.contains("label=\"say \\\"hi\\\"\""));
+    }
+}
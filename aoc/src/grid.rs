@@ -0,0 +1,378 @@
+use std::collections::HashMap;
+
+use crate::map::{IntCoord, Map};
+
+/// Divide, rounding towards negative infinity
+fn div_floor<I: IntCoord>(a: I, b: I) -> I {
+    let q = a / b;
+    if a % b != I::zero() && ((a < I::zero()) != (b < I::zero())) {
+        q - I::one()
+    } else {
+        q
+    }
+}
+
+/// A spatial index over sparse 2D coordinates that supports fast rectangle queries.
+///
+/// Points are grouped into square buckets of `bucket_size`, so a query only needs to look at
+/// the buckets overlapping the queried rectangle.
+#[derive(Debug, Clone)]
+pub struct SparseIndex<I: IntCoord, V> {
+    bucket_size: I,
+    buckets: HashMap<[I; 2], Vec<([I; 2], V)>>,
+    len: usize,
+}
+
+impl<I: IntCoord, V> SparseIndex<I, V> {
+    pub fn new(bucket_size: I) -> Self {
+        assert!(bucket_size > I::zero(), "Bucket size must be positive");
+
+        Self {
+            bucket_size,
+            buckets: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    fn bucket(&self, coord: &[I; 2]) -> [I; 2] {
+        [
+            div_floor(coord[0], self.bucket_size),
+            div_floor(coord[1], self.bucket_size),
+        ]
+    }
+
+    /// Insert a value at a coordinate, returning the previous value at that coordinate
+    pub fn insert(&mut self, coord: [I; 2], val: V) -> Option<V> {
+        let bucket = self.buckets.entry(self.bucket(&coord)).or_default();
+
+        if let Some(entry) = bucket.iter_mut().find(|(c, _)| *c == coord) {
+            return Some(std::mem::replace(&mut entry.1, val));
+        }
+
+        bucket.push((coord, val));
+        self.len += 1;
+        None
+    }
+
+    /// Get the value at a coordinate
+    pub fn get(&self, coord: &[I; 2]) -> Option<&V> {
+        self.buckets
+            .get(&self.bucket(coord))?
+            .iter()
+            .find(|(c, _)| c == coord)
+            .map(|(_, v)| v)
+    }
+
+    /// Get the number of stored points
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Get all points within the rectangle spanned by `min` and `max` (inclusive)
+    pub fn query_rect(&self, min: [I; 2], max: [I; 2]) -> Vec<([I; 2], &V)> {
+        let [bimin, bjmin] = self.bucket(&min);
+        let [bimax, bjmax] = self.bucket(&max);
+
+        // for rectangles spanning more buckets than are occupied, scan the occupied ones instead
+        let n_buckets = bucket_span(bimin, bimax)
+            .zip(bucket_span(bjmin, bjmax))
+            .and_then(|(ni, nj)| ni.checked_mul(nj));
+        let scan_occupied = n_buckets.is_none_or(|n| n > self.buckets.len() as u128);
+
+        let buckets: Vec<&Vec<([I; 2], V)>> = if scan_occupied {
+            self.buckets
+                .iter()
+                .filter(|(b, _)| b[0] >= bimin && b[0] <= bimax && b[1] >= bjmin && b[1] <= bjmax)
+                .map(|(_, bucket)| bucket)
+                .collect()
+        } else {
+            num::iter::range_inclusive(bimin, bimax)
+                .flat_map(|bi| {
+                    num::iter::range_inclusive(bjmin, bjmax)
+                        .filter_map(move |bj| self.buckets.get(&[bi, bj]))
+                })
+                .collect()
+        };
+
+        buckets
+            .into_iter()
+            .flatten()
+            .filter(|(c, _)| c[0] >= min[0] && c[0] <= max[0] && c[1] >= min[1] && c[1] <= max[1])
+            .map(|(c, v)| (*c, v))
+            .collect()
+    }
+}
+
+/// Number of buckets from `lo` to `hi` (inclusive), or `None` if it cannot be counted
+fn bucket_span<I: IntCoord>(lo: I, hi: I) -> Option<u128> {
+    if hi < lo {
+        return Some(0);
+    }
+
+    let span = hi.to_i128()?.checked_sub(lo.to_i128()?)? as u128;
+    span.checked_add(1)
+}
+
+/// A 2D grid addressed by `(x, y)` instead of `[i, j]`.
+///
+/// `x` is the column and `y` the row, with `y` growing downwards, so `get(x, y)` reads the
+/// tile at `[y, x]` of the underlying `Map`. `width()` and `height()` are the number of
+/// columns and rows spanned by the extent.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Grid2D<T> {
+    map: Map<[i32; 2], T>,
+}
+
+impl<T> Grid2D<T> {
+    pub fn new() -> Self {
+        Self { map: Map::new() }
+    }
+
+    /// Get the tile in column `x` of row `y`
+    pub fn get(&self, x: i32, y: i32) -> Option<&T> {
+        self.map.get(&[y, x])
+    }
+
+    /// Get a mutable reference to the tile in column `x` of row `y`
+    pub fn get_mut(&mut self, x: i32, y: i32) -> Option<&mut T> {
+        self.map.get_mut(&[y, x])
+    }
+
+    /// Set the tile in column `x` of row `y`
+    pub fn set(&mut self, x: i32, y: i32, value: T) {
+        self.map.set([y, x], value);
+    }
+
+    /// Get the number of columns spanned by the extent
+    pub fn width(&self) -> usize {
+        self.map
+            .try_get_extent()
+            .map_or(0, |(min, max)| (max[1] - min[1] + 1) as usize)
+    }
+
+    /// Get the number of rows spanned by the extent
+    pub fn height(&self) -> usize {
+        self.map
+            .try_get_extent()
+            .map_or(0, |(min, max)| (max[0] - min[0] + 1) as usize)
+    }
+
+    /// Get the underlying `[i, j]` map
+    pub fn as_map(&self) -> &Map<[i32; 2], T> {
+        &self.map
+    }
+
+    pub fn into_map(self) -> Map<[i32; 2], T> {
+        self.map
+    }
+}
+
+impl<T> From<Map<[i32; 2], T>> for Grid2D<T> {
+    fn from(map: Map<[i32; 2], T>) -> Self {
+        Self { map }
+    }
+}
+
+/// A small fixed-size display of pixels that are either lit or dark, stored as packed bits
+/// in row-major order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screen {
+    width: usize,
+    height: usize,
+    bits: Vec<u64>,
+}
+
+impl Screen {
+    /// Create a screen with all pixels dark
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            bits: vec![0; (width * height).div_ceil(64)],
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Check whether the pixel in column `x` of row `y` is lit
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        assert!(x < self.width && y < self.height, "Pixel out of bounds");
+        let k = y * self.width + x;
+        self.bits[k / 64] & (1 << (k % 64)) != 0
+    }
+
+    /// Light or darken the pixel in column `x` of row `y`
+    pub fn set(&mut self, x: usize, y: usize, lit: bool) {
+        assert!(x < self.width && y < self.height, "Pixel out of bounds");
+        let k = y * self.width + x;
+        if lit {
+            self.bits[k / 64] |= 1 << (k % 64);
+        } else {
+            self.bits[k / 64] &= !(1 << (k % 64));
+        }
+    }
+
+    /// Light all pixels of the `w` by `h` rectangle in the top-left corner
+    pub fn rect(&mut self, w: usize, h: usize) {
+        for y in 0..h.min(self.height) {
+            for x in 0..w.min(self.width) {
+                self.set(x, y, true);
+            }
+        }
+    }
+
+    /// Shift the pixels of row `y` right by `by`, wrapping around at the right edge
+    pub fn rotate_row(&mut self, y: usize, by: usize) {
+        let row: Vec<bool> = (0..self.width).map(|x| self.get(x, y)).collect();
+        for (x, lit) in row.into_iter().enumerate() {
+            self.set((x + by) % self.width, y, lit);
+        }
+    }
+
+    /// Shift the pixels of column `x` down by `by`, wrapping around at the bottom edge
+    pub fn rotate_col(&mut self, x: usize, by: usize) {
+        let col: Vec<bool> = (0..self.height).map(|y| self.get(x, y)).collect();
+        for (y, lit) in col.into_iter().enumerate() {
+            self.set(x, (y + by) % self.height, lit);
+        }
+    }
+
+    /// Count the lit pixels
+    pub fn count_lit(&self) -> usize {
+        self.bits.iter().map(|b| b.count_ones() as usize).sum()
+    }
+}
+
+/// Render lit pixels as `#` and dark pixels as `.`, one line per row
+impl std::fmt::Display for Screen {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                write!(f, "{}", if self.get(x, y) { '#' } else { '.' })?;
+            }
+            writeln!(f)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_div_floor() {
+        assert_eq!(div_floor(7, 4), 1);
+        assert_eq!(div_floor(-1, 4), -1);
+        assert_eq!(div_floor(-4, 4), -1);
+        assert_eq!(div_floor(-5, 4), -2);
+        assert_eq!(div_floor(5usize, 4), 1);
+    }
+
+    #[test]
+    fn test_sparse_index() {
+        let mut index: SparseIndex<i64, char> = SparseIndex::new(4);
+        for (k, c) in [
+            ([0, 0], 'a'),
+            ([-1, 3], 'b'),
+            ([2, 2], 'c'),
+            ([5, -3], 'd'),
+            ([100, 100], 'e'),
+        ] {
+            index.insert(k, c);
+        }
+
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.insert([2, 2], 'C'), Some('c'));
+        assert_eq!(index.len(), 5);
+        assert_eq!(index.get(&[2, 2]), Some(&'C'));
+        assert_eq!(index.get(&[2, 3]), None);
+
+        let mut found = index.query_rect([-1, -3], [2, 3]);
+        found.sort();
+        assert_eq!(found, vec![([-1, 3], &'b'), ([0, 0], &'a'), ([2, 2], &'C')]);
+
+        // points just outside the rectangle are excluded
+        let mut found = index.query_rect([0, -2], [5, 2]);
+        found.sort();
+        assert_eq!(found, vec![([0, 0], &'a'), ([2, 2], &'C')]);
+
+        assert_eq!(index.query_rect([6, 6], [99, 99]), vec![]);
+
+        // rectangles covering many more buckets than are occupied give the same results
+        let mut found = index.query_rect([-1000, -1000], [1000, 3]);
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                ([-1, 3], &'b'),
+                ([0, 0], &'a'),
+                ([2, 2], &'C'),
+                ([5, -3], &'d')
+            ]
+        );
+
+        let found = index.query_rect([i64::MIN, i64::MIN], [i64::MAX, i64::MAX]);
+        assert_eq!(found.len(), 5);
+    }
+
+    #[test]
+    fn test_grid2d() {
+        let mut grid: Grid2D<char> = Grid2D::new();
+        assert_eq!((grid.width(), grid.height()), (0, 0));
+
+        grid.set(3, 1, 'a');
+        grid.set(0, 0, 'b');
+        assert_eq!(grid.get(3, 1), Some(&'a'));
+        assert_eq!(grid.get(1, 3), None);
+        assert_eq!((grid.width(), grid.height()), (4, 2));
+
+        *grid.get_mut(0, 0).unwrap() = 'c';
+
+        // x is the column and y the row of the underlying map
+        assert_eq!(grid.as_map().get(&[1, 3]), Some(&'a'));
+        assert_eq!(grid.as_map().get(&[0, 0]), Some(&'c'));
+
+        let map: Map<[i32; 2], char> = "ab\ncd\nef\n".parse().unwrap();
+        let grid: Grid2D<char> = map.into();
+        assert_eq!(grid.get(1, 2), Some(&'f'));
+        assert_eq!((grid.width(), grid.height()), (2, 3));
+    }
+
+    #[test]
+    fn test_screen() {
+        let mut screen = Screen::new(7, 3);
+
+        screen.rect(3, 2);
+        assert_eq!(screen.to_string(), "###....\n###....\n.......\n");
+
+        screen.rotate_col(1, 1);
+        assert_eq!(screen.to_string(), "#.#....\n###....\n.#.....\n");
+
+        screen.rotate_row(0, 4);
+        assert_eq!(screen.to_string(), "....#.#\n###....\n.#.....\n");
+
+        screen.rotate_col(1, 1);
+        assert_eq!(screen.to_string(), ".#..#.#\n#.#....\n.#.....\n");
+
+        assert_eq!(screen.count_lit(), 6);
+        assert!(screen.get(6, 0) && !screen.get(5, 0));
+
+        // screens spanning several words of bits
+        let mut screen = Screen::new(50, 6);
+        screen.rect(50, 6);
+        screen.set(49, 5, false);
+        assert_eq!(screen.count_lit(), 299);
+    }
+}
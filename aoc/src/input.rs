@@ -1,41 +1,72 @@
-use std::{fs::File, io::Write};
+use std::{
+    fs::File,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{anyhow, Context, Result};
 use reqwest::header::{COOKIE, USER_AGENT};
 
 use crate::config::Config;
-
-pub trait InputSource {
-    fn get_input(&self) -> Result<File>;
+use crate::io::{IoError, Result};
+
+/// Where to obtain puzzle input from.
+///
+/// Inputs are resolved in the following order:
+///
+///   1. A `Path` is read from disk as-is.
+///   2. A `Day` is read from its cache file `data/dayXX/input` if that exists.
+///   3. Otherwise, a `Day` is downloaded from adventofcode.com (rate-limited, see
+///      `Config::rate_limit`) and stored in the cache file for future use.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InputSource {
+    /// A file on disk
+    Path(PathBuf),
+
+    /// The puzzle input for a (year, day)
+    Day(u16, u8),
 }
 
 /// Provide a file path as an InputSource
-impl InputSource for &str {
-    fn get_input(&self) -> Result<File> {
-        File::open(self).context("Load input from disk")
+impl From<&str> for InputSource {
+    fn from(path: &str) -> Self {
+        InputSource::Path(path.into())
+    }
+}
+
+impl From<&Path> for InputSource {
+    fn from(path: &Path) -> Self {
+        InputSource::Path(path.to_owned())
+    }
+}
+
+impl From<PathBuf> for InputSource {
+    fn from(path: PathBuf) -> Self {
+        InputSource::Path(path)
     }
 }
 
 /// Provide a (year, day) tuple as an InputSource
-impl InputSource for (usize, usize) {
-    fn get_input(&self) -> Result<File> {
-        let &(year, day) = self;
+impl From<(u16, u8)> for InputSource {
+    fn from((year, day): (u16, u8)) -> Self {
+        InputSource::Day(year, day)
+    }
+}
 
-        // try to obtain the input from the filesystem
-        let cache_folder = format!("data/day{:02}", day);
-        std::fs::create_dir_all(cache_folder)?;
+/// A way of obtaining puzzle inputs that are not cached yet
+pub trait Fetcher {
+    fn fetch(&self, year: u16, day: u8) -> Result<String>;
+}
 
-        let cache_path = format!("data/day{:02}/input", day);
-        if let Ok(file) = (cache_path.as_str()).get_input() {
-            return Ok(file);
-        }
+/// Download puzzle inputs from adventofcode.com using the session token from the config
+pub struct HttpFetcher;
 
-        let mut config = Config::load()?;
-        config.rate_limit()?;
+impl Fetcher for HttpFetcher {
+    fn fetch(&self, year: u16, day: u8) -> Result<String> {
+        let fetch_error = |e: anyhow::Error| IoError::Fetch { source: e.into() };
 
-        let session_token = &config.session_token.ok_or_else(|| {
-            anyhow!("No session token! Need to log in using `aoc login` CLI command")
-        })?;
+        let mut config = Config::load().map_err(fetch_error)?;
+        config.rate_limit().map_err(fetch_error)?;
+
+        let session_token = &config.session_token.ok_or(IoError::MissingSession)?;
 
         let client = reqwest::blocking::Client::new();
 
@@ -49,19 +80,135 @@ impl InputSource for (usize, usize) {
                 "https://github.com/sseemayer/aoc by mail@semicolonsoftware.de",
             )
             .header(COOKIE, format!("session={}", session_token))
-            .send()?
-            .error_for_status()?;
+            .send()
+            .and_then(|res| res.error_for_status())
+            .map_err(|e| IoError::Fetch { source: e.into() })?;
+
+        res.text().map_err(|e| IoError::Fetch { source: e.into() })
+    }
+}
+
+impl InputSource {
+    /// Open the input, caching downloaded inputs in the `data` folder
+    pub fn open(&self) -> Result<File> {
+        self.open_with(Path::new("data"), &HttpFetcher)
+    }
 
-        let content = res.text()?;
+    /// Open the input, caching downloaded inputs below `cache_dir` and obtaining uncached
+    /// inputs from `fetcher`. Fails with `IoError::InvalidDay` for days that have no puzzle.
+    pub fn open_with(&self, cache_dir: &Path, fetcher: &dyn Fetcher) -> Result<File> {
+        match self {
+            InputSource::Path(path) => File::open(path).map_err(|e| match e.kind() {
+                std::io::ErrorKind::NotFound => IoError::NotFound { path: path.clone() },
+                _ => e.into(),
+            }),
+            InputSource::Day(year, day) => {
+                if *year < 2015 || !(1..=25).contains(day) {
+                    return Err(IoError::InvalidDay {
+                        year: *year,
+                        day: *day,
+                    });
+                }
+
+                // try to obtain the input from the filesystem
+                let cache_folder = cache_dir.join(format!("day{:02}", day));
+                let cache_path = cache_folder.join("input");
+                if let Ok(file) = File::open(&cache_path) {
+                    return Ok(file);
+                }
+
+                let content = fetcher.fetch(*year, *day)?;
+
+                std::fs::create_dir_all(&cache_folder)?;
+                std::fs::write(&cache_path, content)?;
+
+                Ok(File::open(cache_path)?)
+            }
+        }
+    }
+}
 
-        let mut file = File::create(&cache_path).context("Create file to store API result")?;
-        file.write_all(content.as_bytes())
-            .context("Write input to cache")?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::Cell, io::Read};
 
-        std::mem::forget(file);
+    struct MockFetcher {
+        calls: Cell<usize>,
+    }
 
-        let file = File::open(cache_path)?;
+    impl Fetcher for MockFetcher {
+        fn fetch(&self, year: u16, day: u8) -> Result<String> {
+            self.calls.set(self.calls.get() + 1);
+            Ok(format!("input for {} day {}", year, day))
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("aoc-input-test-{}-{}", std::process::id(), name))
+    }
+
+    fn read_to_string(mut file: File) -> String {
+        let mut buf = String::new();
+        file.read_to_string(&mut buf).unwrap();
+        buf
+    }
+
+    #[test]
+    fn test_path_source() {
+        let path = temp_path("path");
+        std::fs::write(&path, "1\n2\n3\n").unwrap();
+
+        let source: InputSource = path.as_path().into();
+        assert_eq!(source, InputSource::Path(path.clone()));
+        assert_eq!(read_to_string(source.open().unwrap()), "1\n2\n3\n");
+
+        let lines: Vec<u32> = crate::io::read_lines(path.to_str().unwrap()).unwrap();
+        assert_eq!(lines, vec![1, 2, 3]);
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_day_source() {
+        let cache_dir = temp_path("cache");
+        let fetcher = MockFetcher {
+            calls: Cell::new(0),
+        };
+
+        let source: InputSource = (2024, 7).into();
+        assert_eq!(source, InputSource::Day(2024, 7));
+
+        // first access fetches and caches the input, second access uses the cache
+        for _ in 0..2 {
+            let file = source.open_with(&cache_dir, &fetcher).unwrap();
+            assert_eq!(read_to_string(file), "input for 2024 day 7");
+        }
+
+        assert_eq!(fetcher.calls.get(), 1);
+        assert!(cache_dir.join("day07").join("input").exists());
+
+        std::fs::remove_dir_all(cache_dir).unwrap();
+    }
+
+    #[test]
+    fn test_source_errors() {
+        let missing: InputSource = temp_path("missing").into();
+        assert!(matches!(missing.open(), Err(IoError::NotFound { .. })));
+
+        let fetcher = MockFetcher {
+            calls: Cell::new(0),
+        };
+        let cache_dir = temp_path("invalid");
+        for (year, day) in [(2024, 0), (2024, 26), (2014, 1)] {
+            let source: InputSource = (year, day).into();
+            assert!(matches!(
+                source.open_with(&cache_dir, &fetcher),
+                Err(IoError::InvalidDay { .. })
+            ));
+        }
 
-        Ok(file)
+        assert_eq!(fetcher.calls.get(), 0);
+        assert!(!cache_dir.exists());
     }
 }
@@ -1,25 +1,74 @@
-use anyhow::{anyhow, Result};
-
 use std::{
+    collections::HashMap,
+    convert::TryInto,
     io::{BufRead, BufReader, Read},
+    path::PathBuf,
     str::FromStr,
 };
 
+use thiserror::Error;
+
 use crate::input::InputSource;
+use crate::map::{Map, MapError, ParseMapTile};
+
+#[derive(Debug, Error)]
+pub enum IoError {
+    #[error("Input file not found: {}", path.display())]
+    NotFound { path: PathBuf },
+
+    #[error("No puzzle for year {} day {}", year, day)]
+    InvalidDay { year: u16, day: u8 },
+
+    #[error("No session token! Need to log in using `aoc login` CLI command")]
+    MissingSession,
+
+    #[error("Failed to fetch input: {}", source)]
+    Fetch {
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// A value could not be parsed. `line` is the 0-based index of the offending input line,
+    /// like in `MapError::BadTile`, or `None` when parsing a value on its own.
+    #[error(
+        "Parse error{}: {}",
+        line.map(|l| format!(" at line {}", l)).unwrap_or_default(),
+        message
+    )]
+    Parse {
+        line: Option<usize>,
+        message: String,
+    },
+
+    #[error(transparent)]
+    Map {
+        #[from]
+        source: MapError,
+    },
+
+    #[error("I/O error: {}", source)]
+    Io {
+        #[from]
+        source: std::io::Error,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, IoError>;
 
-pub fn read_all<S: InputSource>(source: S) -> Result<String> {
-    let mut file = source.get_input()?;
+/// Read an entire input into a string. See `InputSource` for how inputs are resolved.
+pub fn read_all<S: Into<InputSource>>(source: S) -> Result<String> {
+    let mut file = source.into().open()?;
     let mut buf = String::new();
     file.read_to_string(&mut buf)?;
 
     Ok(buf)
 }
 
-pub fn read_lines<T: FromStr, S: InputSource>(source: S) -> Result<Vec<T>>
+/// Parse all non-empty lines of an input. See `InputSource` for how inputs are resolved.
+pub fn read_lines<T: FromStr, S: Into<InputSource>>(source: S) -> Result<Vec<T>>
 where
     <T as FromStr>::Err: std::fmt::Display,
 {
-    read_lines_reader(source.get_input()?)
+    read_lines_reader(source.into().open()?)
 }
 
 pub fn read_lines_reader<T: FromStr, R: Read>(r: R) -> Result<Vec<T>>
@@ -33,17 +82,271 @@ pub fn read_lines_bufreader<T: FromStr, R: BufRead>(reader: R) -> Result<Vec<T>>
 where
     <T as FromStr>::Err: std::fmt::Display,
 {
-    reader
-        .lines()
-        .filter_map(|l| {
-            let l = l.ok()?;
-            let l = l.trim();
-            if l.is_empty() {
-                None
-            } else {
-                Some(l.to_owned())
-            }
+    let mut out = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let value = <T as FromStr>::from_str(line).map_err(|e| IoError::Parse {
+            line: Some(i),
+            message: e.to_string(),
+        })?;
+
+        out.push(value);
+    }
+
+    Ok(out)
+}
+
+/// Parse an input consisting of a map, a blank line and a trailing section (e.g. a list of
+/// movement instructions), returning the map and the trimmed trailing section.
+///
+/// The input is split at the last line that is empty or only contains whitespace, so leading
+/// whitespace in the map is preserved. Both `\n` and `\r\n` line endings are supported.
+pub fn read_map_and_tail<T: ParseMapTile>(input: &str) -> Result<(Map<[i32; 2], T>, String)> {
+    let lines: Vec<&str> = input.trim_end().lines().collect();
+    let split = lines
+        .iter()
+        .rposition(|l| l.trim().is_empty())
+        .ok_or_else(|| IoError::Parse {
+            line: None,
+            message: "Expected a blank line between map and tail".to_string(),
+        })?;
+
+    let map = lines[..split].join("\n");
+    let tail = lines[split + 1..].join("\n");
+
+    Ok((map.parse()?, tail.trim().to_owned()))
+}
+
+/// Split an input into a grid of characters, one row per line. Rows keep their own length.
+pub fn char_grid(input: &str) -> Vec<Vec<char>> {
+    input.lines().map(|l| l.chars().collect()).collect()
+}
+
+/// Parse a grid of single decimal digits into a map of values `0..=9`, failing with the
+/// position of the first non-digit character
+pub fn parse_digit_grid(input: &str) -> Result<Map<[i32; 2], u8>> {
+    let mut map = Map::new();
+
+    for (i, line) in input.lines().enumerate() {
+        for (j, c) in line.trim_end().chars().enumerate() {
+            let d = c.to_digit(10).ok_or(MapError::BadTile {
+                line: i,
+                col: j,
+                ch: c,
+            })?;
+
+            map.set([i as i32, j as i32], d as u8);
+        }
+    }
+
+    Ok(map)
+}
+
+/// Parse exactly `N` values separated by `sep` into an array, trimming whitespace around
+/// each value. A whitespace `sep` splits at any run of whitespace.
+pub fn parse_array<const N: usize, T: FromStr>(s: &str, sep: char) -> Result<[T; N]>
+where
+    <T as FromStr>::Err: std::fmt::Display,
+{
+    let parts: Vec<&str> = if sep.is_whitespace() {
+        s.split_whitespace().collect()
+    } else {
+        s.trim().split(sep).map(|p| p.trim()).collect()
+    };
+
+    let values = parts
+        .into_iter()
+        .map(|p| {
+            p.parse().map_err(|e: <T as FromStr>::Err| IoError::Parse {
+                line: None,
+                message: format!("Bad value '{}': {}", p, e),
+            })
         })
-        .map(|l| <T as FromStr>::from_str(&l).map_err(|e| anyhow!("Parse error: {}", e)))
-        .collect::<Result<Vec<T>>>()
+        .collect::<Result<Vec<T>>>()?;
+
+    let count = values.len();
+    values.try_into().map_err(|_| IoError::Parse {
+        line: None,
+        message: format!("Expected {} values, got {}", N, count),
+    })
+}
+
+/// Parse an inclusive range `a-b` into `(a, b)`. Both bounds may be negative, e.g. `-3--1`.
+pub fn parse_range(s: &str) -> Result<(i64, i64)> {
+    let s = s.trim();
+    let bad_range = |message: String| IoError::Parse {
+        line: None,
+        message,
+    };
+
+    let split = s
+        .char_indices()
+        .skip(1)
+        .find(|(_, c)| *c == '-')
+        .map(|(i, _)| i)
+        .ok_or_else(|| bad_range(format!("Expected a range 'a-b', got '{}'", s)))?;
+
+    let parse_bound = |b: &str| {
+        b.parse::<i64>()
+            .map_err(|e| bad_range(format!("Bad range bound '{}': {}", b, e)))
+    };
+
+    Ok((parse_bound(&s[..split])?, parse_bound(&s[split + 1..])?))
+}
+
+/// Parse a comma-separated pair of inclusive ranges such as `2-4,6-8`
+pub fn parse_range_pair(s: &str) -> Result<((i64, i64), (i64, i64))> {
+    let [a, b]: [String; 2] = parse_array(s, ',')?;
+    Ok((parse_range(&a)?, parse_range(&b)?))
+}
+
+/// Parse blocks of whitespace-separated `key:value` fields, with blocks separated by blank
+/// lines. Fields may be spread over several lines, and tokens without a `:` are ignored.
+pub fn parse_kv_blocks(input: &str) -> Vec<HashMap<String, String>> {
+    let mut out = Vec::new();
+    let mut block = HashMap::new();
+
+    for line in input.lines() {
+        if line.trim().is_empty() {
+            if !block.is_empty() {
+                out.push(std::mem::take(&mut block));
+            }
+            continue;
+        }
+
+        for token in line.split_whitespace() {
+            if let Some((k, v)) = token.split_once(':') {
+                block.insert(k.to_string(), v.to_string());
+            }
+        }
+    }
+
+    if !block.is_empty() {
+        out.push(block);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_map_and_tail() {
+        let (map, tail) = read_map_and_tail::<char>("  .#\n#..\n\n10R5L\n").unwrap();
+
+        assert_eq!(tail, "10R5L");
+        assert_eq!(map.get_extent(), ([0, 0], [1, 3]));
+        assert_eq!(map.get(&[0, 1]), None);
+        assert_eq!(map.get(&[0, 3]), Some(&'#'));
+        assert_eq!(map.get(&[1, 0]), Some(&'#'));
+
+        assert!(read_map_and_tail::<char>("..\n##\n10R5L").is_err());
+
+        // CRLF line endings and a separator line containing only whitespace
+        let (crlf, tail) = read_map_and_tail::<char>("  .#\r\n#..\r\n \t\r\n10R5L\r\n").unwrap();
+        assert_eq!(tail, "10R5L");
+        assert_eq!(crlf, map);
+    }
+
+    #[test]
+    fn test_char_grid() {
+        let grid = char_grid("ab#\n.\n\nxyzw\n");
+
+        assert_eq!(grid.len(), 4);
+        assert_eq!(
+            grid.iter().map(|row| row.len()).collect::<Vec<_>>(),
+            vec![3, 1, 0, 4]
+        );
+        assert_eq!(grid[0], vec!['a', 'b', '#']);
+        assert_eq!(grid[3][3], 'w');
+    }
+
+    #[test]
+    fn test_parse_digit_grid() {
+        let map = parse_digit_grid("219\n398\n").unwrap();
+
+        assert_eq!(map.get_extent(), ([0, 0], [1, 2]));
+        assert_eq!(map.get(&[0, 0]), Some(&2));
+        assert_eq!(map.get(&[1, 2]), Some(&8));
+
+        let err = parse_digit_grid("219\n3x8\n").unwrap_err();
+        assert_eq!(err.to_string(), "Bad tile 'x' at line 1, column 1");
+    }
+
+    #[test]
+    fn test_read_lines_errors() {
+        let lines: Vec<u32> = read_lines_reader("1\n\n2\n".as_bytes()).unwrap();
+        assert_eq!(lines, vec![1, 2]);
+
+        let err = read_lines_reader::<u32, _>("1\n\nx\n".as_bytes()).unwrap_err();
+        assert!(matches!(err, IoError::Parse { line: Some(2), .. }));
+        assert_eq!(
+            err.to_string(),
+            "Parse error at line 2: invalid digit found in string"
+        );
+
+        assert!(matches!(
+            parse_digit_grid("1x"),
+            Err(IoError::Map {
+                source: MapError::BadTile { .. }
+            })
+        ));
+    }
+
+    #[test]
+    fn test_parse_array() {
+        assert_eq!(parse_array::<3, i64>("1,2,3", ',').unwrap(), [1, 2, 3]);
+        assert_eq!(
+            parse_array::<3, i64>(" -1, 2 ,3\n", ',').unwrap(),
+            [-1, 2, 3]
+        );
+        assert_eq!(parse_array::<2, u8>("4   5", ' ').unwrap(), [4, 5]);
+
+        let err = parse_array::<3, i64>("1,2", ',').unwrap_err();
+        assert_eq!(err.to_string(), "Parse error: Expected 3 values, got 2");
+
+        assert!(parse_array::<3, i64>("1,2,3,4", ',').is_err());
+        let err = parse_array::<2, i64>("1,x", ',').unwrap_err();
+        assert!(matches!(err, IoError::Parse { line: None, .. }));
+        assert_eq!(
+            err.to_string(),
+            "Parse error: Bad value 'x': invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("2-4").unwrap(), (2, 4));
+        assert_eq!(parse_range(" 10-100\n").unwrap(), (10, 100));
+        assert_eq!(parse_range("-3--1").unwrap(), (-3, -1));
+
+        assert!(parse_range("2").is_err());
+        assert!(parse_range("2-").is_err());
+        assert!(parse_range("a-4").is_err());
+        assert!(parse_range("2-4-6").is_err());
+
+        assert_eq!(parse_range_pair("2-4,6-8").unwrap(), ((2, 4), (6, 8)));
+        assert!(parse_range_pair("2-4").is_err());
+    }
+
+    #[test]
+    fn test_parse_kv_blocks() {
+        let input = "ecl:gry pid:860033327 eyr:2020 hcl:#fffffd\nbyr:1937 iyr:2017 cid:147 hgt:183cm\n\niyr:2013 ecl:amb\n  \n\nhcl:#cfa07d byr:1929\n";
+        let blocks = parse_kv_blocks(input);
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[0].len(), 8);
+        assert_eq!(blocks[0]["hcl"], "#fffffd");
+        assert_eq!(blocks[0]["hgt"], "183cm");
+        assert_eq!(blocks[1].len(), 2);
+        assert_eq!(blocks[1]["ecl"], "amb");
+        assert_eq!(blocks[2]["byr"], "1929");
+    }
 }
@@ -1,7 +1,14 @@
+pub mod chronospatial;
 pub mod config;
 pub mod direction;
+pub mod graph;
+pub mod grid;
 pub mod input;
 pub mod io;
 pub mod map;
 pub mod math;
+pub mod ocr;
+pub mod registry;
+pub mod search;
 pub mod summed_area_table;
+pub mod util;
@@ -1,8 +1,14 @@
-use std::collections::HashMap;
+use std::cmp::Reverse;
+use std::collections::{hash_map::RandomState, BinaryHeap, HashMap, HashSet};
+use std::hash::BuildHasher;
 
+use std::collections::VecDeque;
 use std::io::{BufRead, BufReader};
+use strum::IntoEnumIterator;
 use thiserror::Error;
 
+use crate::direction::Direction;
+
 #[derive(Debug, Error)]
 pub enum MapError {
     #[error("I/O error: {}", source)]
@@ -10,10 +16,49 @@ pub enum MapError {
         #[from]
         source: std::io::Error,
     },
+
+    #[error("Bad tile '{}' at line {}, column {}", ch, line, col)]
+    BadTile { line: usize, col: usize, ch: char },
 }
 
 type MapResult<T> = std::result::Result<T, MapError>;
 
+/// Distances and predecessors of all coordinates reached during a search
+type BfsResult<C> = (HashMap<C, usize>, HashMap<C, C>);
+
+/// A hasher for the tile storage of maps
+pub trait MapHasher: BuildHasher + Default + Clone {}
+
+impl<S> MapHasher for S where S: BuildHasher + Default + Clone {}
+
+/// Storage for the tiles of a map, hashing coordinates with `S`
+pub type MapData<C, T, S = RandomState> = HashMap<C, T, S>;
+
+/// The faster (but not DoS-resistant) FxHash, which speeds up lookup-heavy simulations
+/// considerably. Maps using it are created with `Map::with_fast_hasher`.
+#[cfg(feature = "fxhash")]
+pub type FastHasher = rustc_hash::FxBuildHasher;
+
+/// A way of measuring distances between grid coordinates
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Metric {
+    /// Sum of the absolute coordinate differences, i.e. 4-neighborhood steps
+    Manhattan,
+
+    /// Maximum of the absolute coordinate differences, i.e. 8-neighborhood steps
+    Chebyshev,
+}
+
+impl Metric {
+    /// Measure the length of an offset `[di, dj]`
+    pub fn norm(&self, [di, dj]: [i64; 2]) -> u64 {
+        match self {
+            Metric::Manhattan => di.unsigned_abs() + dj.unsigned_abs(),
+            Metric::Chebyshev => di.unsigned_abs().max(dj.unsigned_abs()),
+        }
+    }
+}
+
 /// Trait for a generic integer coordinate
 pub trait IntCoord:
     num::PrimInt
@@ -77,21 +122,53 @@ pub trait MapCoordinate: Default + Eq + std::hash::Hash + std::fmt::Debug + Clon
     fn extent_iterator(min: Self, max: Self) -> Self::ExtentIter;
 }
 
-/// A tile-based map that is generic over coordinates and tiles stored within
-#[derive(Default, Debug, Clone, Eq, PartialEq)]
-pub struct Map<C: MapCoordinate, T> {
-    pub data: HashMap<C, T>,
+/// A tile-based map that is generic over coordinates and tiles stored within, and over the
+/// hasher used for the tile storage
+#[derive(Default, Debug, Clone)]
+pub struct Map<C: MapCoordinate, T, S = RandomState> {
+    pub data: MapData<C, T, S>,
     pub fixed_extent: Option<(C, C)>,
 }
 
+impl<C: MapCoordinate, T: PartialEq, S: BuildHasher> PartialEq for Map<C, T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data && self.fixed_extent == other.fixed_extent
+    }
+}
+
+impl<C: MapCoordinate, T: Eq, S: BuildHasher> Eq for Map<C, T, S> {}
+
 impl<C: MapCoordinate, T> Map<C, T> {
     pub fn new() -> Self {
         Map {
-            data: HashMap::new(),
+            data: MapData::default(),
+            fixed_extent: None,
+        }
+    }
+}
+
+#[cfg(feature = "fxhash")]
+impl<C: MapCoordinate, T> Map<C, T, FastHasher> {
+    /// Create an empty map that stores its tiles using FxHash, see `FastHasher`
+    pub fn with_fast_hasher() -> Self {
+        Self::with_hasher(FastHasher::default())
+    }
+}
+
+impl<C: MapCoordinate, T, S: MapHasher> Map<C, T, S> {
+    /// Create an empty map that stores its tiles using the given hasher
+    pub fn with_hasher(hasher: S) -> Self {
+        Map {
+            data: MapData::with_hasher(hasher),
             fixed_extent: None,
         }
     }
 
+    /// Create an empty map that uses the same kind of hasher as this one
+    fn empty_like<U>(&self) -> Map<C, U, S> {
+        Map::with_hasher(self.data.hasher().clone())
+    }
+
     /// Get the tile at a coordinate
     pub fn get(&self, coord: &C) -> Option<&T> {
         self.data.get(coord)
@@ -107,6 +184,11 @@ impl<C: MapCoordinate, T> Map<C, T> {
         self.data.insert(coord, value);
     }
 
+    /// Set the tile at a coordinate, returning the tile that was there before
+    pub fn replace(&mut self, coord: C, value: T) -> Option<T> {
+        self.data.insert(coord, value)
+    }
+
     /// Clear a coordinate from tiles
     pub fn remove(&mut self, coord: &C) {
         self.data.remove(coord);
@@ -121,6 +203,15 @@ impl<C: MapCoordinate, T> Map<C, T> {
         }
     }
 
+    /// Get the extent of the map, or `None` for a map without tiles or fixed extent
+    pub fn try_get_extent(&self) -> Option<(C, C)> {
+        if self.fixed_extent.is_none() && self.data.is_empty() {
+            None
+        } else {
+            Some(self.get_extent())
+        }
+    }
+
     /// Find all coordinates that match a predicate
     pub fn find_all_where<P: Fn(&C, &T) -> bool>(&self, predicate: P) -> Vec<C> {
         let mut out: Vec<C> = Vec::new();
@@ -141,9 +232,100 @@ impl<C: MapCoordinate, T> Map<C, T> {
         }
         None
     }
+
+    /// Move all tiles into a different coordinate system.
+    ///
+    /// If several coordinates are projected onto the same target coordinate, the last write
+    /// wins in arbitrary order - use `map_keys_checked` to detect this. The fixed extent is
+    /// not carried over.
+    pub fn map_keys<D, F>(&self, f: F) -> Map<D, T>
+    where
+        D: MapCoordinate,
+        F: Fn(&C) -> D,
+        T: Clone,
+    {
+        let data = self.data.iter().map(|(k, t)| (f(k), t.clone())).collect();
+
+        Map {
+            data,
+            fixed_extent: None,
+        }
+    }
+
+    /// Move all tiles into a different coordinate system, returning `None` if two tiles
+    /// would be projected onto the same coordinate
+    pub fn map_keys_checked<D, F>(&self, f: F) -> Option<Map<D, T>>
+    where
+        D: MapCoordinate,
+        F: Fn(&C) -> D,
+        T: Clone,
+    {
+        let mut data = MapData::default();
+        for (k, t) in self.data.iter() {
+            if data.insert(f(k), t.clone()).is_some() {
+                return None;
+            }
+        }
+
+        Some(Map {
+            data,
+            fixed_extent: None,
+        })
+    }
+
+    /// Get the sorted coordinates that only have a tile in `self` and those that only have a
+    /// tile in `other`, regardless of tile values
+    pub fn symmetric_difference<U, S2>(&self, other: &Map<C, U, S2>) -> (Vec<C>, Vec<C>)
+    where
+        C: Ord,
+        S2: BuildHasher,
+    {
+        let mut only_self: Vec<C> = self
+            .data
+            .keys()
+            .filter(|k| !other.data.contains_key(k))
+            .copied()
+            .collect();
+
+        let mut only_other: Vec<C> = other
+            .data
+            .keys()
+            .filter(|k| !self.data.contains_key(k))
+            .copied()
+            .collect();
+
+        only_self.sort();
+        only_other.sort();
+
+        (only_self, only_other)
+    }
+
+    /// Keep only the tiles at coordinates that also have a tile in `other`
+    pub fn intersect_keys<U, S2>(&self, other: &Map<C, U, S2>) -> Self
+    where
+        T: Clone,
+        S2: BuildHasher,
+    {
+        let mut out = self.empty_like();
+        out.data.extend(
+            self.data
+                .iter()
+                .filter(|(k, _)| other.data.contains_key(k))
+                .map(|(k, t)| (*k, t.clone())),
+        );
+        out
+    }
+
+    /// Update every tile in place. Tiles are visited in arbitrary order, so `f` should only
+    /// depend on the tile it is given.
+    pub fn for_each_mut<F: FnMut(&C, &mut T)>(&mut self, mut f: F) {
+        for (k, t) in self.data.iter_mut() {
+            f(k, t);
+        }
+    }
 }
 
-impl<C: MapCoordinate, T: Eq> Map<C, T> {
+impl<C: MapCoordinate, T: Eq, S: MapHasher> Map<C, T, S> {
     /// Find all coordinates that contain a tile
     pub fn find_all(&self, pattern: &T) -> Vec<C> {
         self.find_all_where(|_, t| t == pattern)
@@ -155,6 +337,155 @@ impl<C: MapCoordinate, T: Eq> Map<C, T> {
     }
 }
 
+impl<C: MapCoordinate, T: Eq + std::hash::Hash + Clone, S: MapHasher> Map<C, T, S> {
+    /// Get the set of distinct tiles present in the map
+    pub fn tile_set(&self) -> HashSet<T> {
+        self.data.values().cloned().collect()
+    }
+}
+
+impl<C: MapCoordinate + Ord, T: std::hash::Hash, S: MapHasher> Map<C, T, S> {
+    /// Calculate a hash of the tile layout that does not depend on the iteration order of
+    /// the tile storage, e.g. for detecting repeated states in simulations
+    pub fn signature(&self) -> u64 {
+        use std::hash::{Hash, Hasher};
+
+        let mut tiles: Vec<(&C, &T)> = self.data.iter().collect();
+        tiles.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut hasher = std::collections::hash_map::DefaultHasher::new();
+        tiles.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+/// Get the first coordinate of an extent, or `None` if the extent is empty
+fn extent_start<I: IntCoord, const N: usize>(min: [I; N], max: [I; N]) -> Option<[I; N]> {
+    if min.iter().zip(max.iter()).all(|(a, b)| a <= b) {
+        Some(min)
+    } else {
+        None
+    }
+}
+
+/// Get the coordinate following `pos` in an extent, with the first axis changing fastest, or
+/// `None` if `pos` was the last coordinate
+fn advance_extent<I: IntCoord, const N: usize>(
+    mut pos: [I; N],
+    min: &[I; N],
+    max: &[I; N],
+) -> Option<[I; N]> {
+    for k in 0..N {
+        match pos[k].checked_add(&I::one()).filter(|v| *v <= max[k]) {
+            Some(v) => {
+                pos[k] = v;
+                return Some(pos);
+            }
+            None => pos[k] = min[k],
+        }
+    }
+
+    None
+}
+
+/// Get the neighbors of an N-dimensional coordinate, either only along the axes or including
+/// all diagonals
+fn neighbors_nd<I: IntCoord, const N: usize>(pos: [I; N], diagonal: bool) -> Vec<[I; N]> {
+    let mut out = Vec::new();
+
+    let mut offset = [-1i64; N];
+    loop {
+        let nonzero = offset.iter().filter(|d| **d != 0).count();
+        if nonzero == 1 || (diagonal && nonzero > 1) {
+            out.extend(checked_offset(pos, offset));
+        }
+
+        // advance the offset like an odometer
+        let Some(k) = offset.iter().position(|d| *d < 1) else {
+            break;
+        };
+        offset[k] += 1;
+        for d in offset.iter_mut().take(k) {
+            *d = -1;
+        }
+    }
+
+    out
+}
+
+impl<T, I, S, const N: usize> Map<[I; N], T, S>
+where
+    [I; N]: MapCoordinate,
+    I: IntCoord,
+    S: MapHasher,
+{
+    /// Mirror the map along an axis, keeping it within the same extent
+    pub fn flip(&self, axis: usize) -> Self
+    where
+        T: Clone,
+    {
+        let (min, max) = self.get_extent();
+
+        let mut out = self.empty_like();
+        for (pos, tile) in self.data.iter() {
+            let mut pos = *pos;
+            // min <= pos <= max, so neither subtraction can overflow
+            pos[axis] = max[axis] - (pos[axis] - min[axis]);
+
+            out.set(pos, tile.clone());
+        }
+
+        out
+    }
+
+    /// Run one generation of a Conway-style cellular automaton over the set of active tiles.
+    ///
+    /// Inactive coordinates with a number of active neighbors in `born` become active, and
+    /// active ones stay active if their number of active neighbors is in `survive`. Neighbors
+    /// are taken along the axes, or including all diagonals if `diagonal` is set.
+    pub fn step_life_sparse(
+        &self,
+        born: &[usize],
+        survive: &[usize],
+        diagonal: bool,
+    ) -> Map<[I; N], ()> {
+        self.step_life_sparse_with(born, survive, |p| neighbors_nd(*p, diagonal))
+    }
+
+    /// Like `step_life_sparse`, but with a custom neighborhood, e.g. for hexagonal grids
+    pub fn step_life_sparse_with<F>(
+        &self,
+        born: &[usize],
+        survive: &[usize],
+        neighbors: F,
+    ) -> Map<[I; N], ()>
+    where
+        F: Fn(&[I; N]) -> Vec<[I; N]>,
+    {
+        let mut counts: HashMap<[I; N], usize> = HashMap::new();
+        for pos in self.data.keys() {
+            for n in neighbors(pos) {
+                *counts.entry(n).or_insert(0) += 1;
+            }
+        }
+
+        let mut out = Map::new();
+        for pos in self.data.keys() {
+            if survive.contains(&counts.get(pos).copied().unwrap_or(0)) {
+                out.set(*pos, ());
+            }
+        }
+
+        for (pos, count) in counts {
+            if !self.data.contains_key(&pos) && born.contains(&count) {
+                out.set(pos, ());
+            }
+        }
+
+        out
+    }
+}
+
 ////// Code for 2D maps
 
 impl<I> MapCoordinate for [I; 2]
@@ -175,7 +506,7 @@ where
         Extent2DIterator {
             min,
             max,
-            current: Some(min.clone()),
+            current: extent_start(min, max),
         }
     }
 }
@@ -196,26 +527,124 @@ where
     type Item = [I; 2];
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(c) = self.current {
-            let mut next = c.clone();
+        let c = self.current?;
+        self.current = advance_extent(c, &self.min, &self.max);
+        Some(c)
+    }
+}
 
-            next[0] = next[0] + I::one();
-            if next[0] > self.max[0] {
-                next[0] = self.min[0];
-                next[1] = next[1] + I::one();
-            }
+/// Get the orthogonal neighbors of a 2D coordinate
+fn neighbors_2d<I: IntCoord>(pos: [I; 2]) -> impl Iterator<Item = [I; 2]> {
+    Direction::iter().filter_map(move |dir| dir.try_step(pos))
+}
 
-            if next[1] > self.max[1] {
-                self.current = None
-            } else {
-                self.current = Some(next)
-            }
+/// Get the orthogonal and diagonal neighbors of a 2D coordinate
+fn neighbors8_2d<I: IntCoord>(pos: [I; 2]) -> impl Iterator<Item = [I; 2]> {
+    (-1..=1)
+        .flat_map(|di| (-1..=1).map(move |dj| [di, dj]))
+        .filter(|&d| d != [0, 0])
+        .filter_map(move |d| checked_offset(pos, d))
+}
 
-            Some(c)
+/// Offset a coordinate by `delta`, or `None` if any component would leave the range of `I`
+pub fn checked_offset<I: IntCoord, const N: usize>(pos: [I; N], delta: [i64; N]) -> Option<[I; N]> {
+    let mut out = pos;
+    for k in 0..N {
+        let abs = I::from_u64(delta[k].unsigned_abs())?;
+        out[k] = if delta[k] < 0 {
+            pos[k].checked_sub(&abs)?
         } else {
-            None
+            pos[k].checked_add(&abs)?
+        };
+    }
+
+    Some(out)
+}
+
+/// Rebuild the path leading to `goal` from a predecessor tree as returned by `Map::bfs_tree`.
+///
+/// The path starts at the root of the tree and ends at `goal`. If `goal` was never reached,
+/// the path only consists of `goal` itself.
+pub fn reconstruct<C: MapCoordinate>(tree: &HashMap<C, C>, goal: C) -> Vec<C> {
+    let mut path = vec![goal];
+    while let Some(prev) = tree.get(path.last().expect("path is never empty")) {
+        path.push(*prev);
+    }
+
+    path.reverse();
+    path
+}
+
+/// Stitch tiles placed on a grid into one big map.
+///
+/// `placements` maps grid positions to tiles of size `tile_size`, with tile-local coordinates
+/// starting at 0. A border of width `cut` is trimmed from every tile before placement, so
+/// tiles end up `tile_size - 2 * cut` apart.
+pub fn assemble<T, I>(
+    placements: &HashMap<[I; 2], Map<[I; 2], T>>,
+    tile_size: [I; 2],
+    cut: I,
+) -> Map<[I; 2], T>
+where
+    T: MapTile,
+    I: IntCoord,
+{
+    let two = I::one() + I::one();
+    let stride = [tile_size[0] - two * cut, tile_size[1] - two * cut];
+
+    let mut out = Map::new();
+    for ([gi, gj], tile) in placements.iter() {
+        for ([ti, tj], t) in tile.data.iter() {
+            if *ti < cut || *tj < cut || *ti >= tile_size[0] - cut || *tj >= tile_size[1] - cut {
+                continue;
+            }
+
+            out.set(
+                [*gi * stride[0] + *ti - cut, *gj * stride[1] + *tj - cut],
+                t.clone(),
+            );
         }
     }
+
+    out
+}
+
+/// Iterate over the coordinates of a counter-clockwise square spiral around the origin,
+/// starting at `[0, 0]` and then going East. Coordinates are `[i, j]` with North at `-i`.
+pub fn spiral() -> impl Iterator<Item = [i64; 2]> {
+    let mut pos = [0i64, 0];
+    let mut dir = Direction::East;
+    let mut run_length = 1;
+    let mut run_left = 1;
+    let mut second_run = false;
+
+    std::iter::once(pos).chain(std::iter::from_fn(move || {
+        let [di, dj] = dir.dpos();
+        pos = [pos[0] + di as i64, pos[1] + dj as i64];
+
+        run_left -= 1;
+        if run_left == 0 {
+            dir = dir.rot_left();
+            if second_run {
+                run_length += 1;
+            }
+            second_run = !second_run;
+            run_left = run_length;
+        }
+
+        Some(pos)
+    }))
+}
+
+/// Iterate over the coordinates of a 2D extent row by row
+fn row_major<I: IntCoord>(min: [I; 2], max: [I; 2]) -> impl Iterator<Item = [I; 2]> {
+    num::iter::range_inclusive(min[0], max[0])
+        .flat_map(move |i| num::iter::range_inclusive(min[1], max[1]).map(move |j| [i, j]))
+}
+
+/// Check whether a 2D coordinate is within an extent
+fn in_extent_2d<I: IntCoord>(pos: &[I; 2], (min, max): &([I; 2], [I; 2])) -> bool {
+    pos[0] >= min[0] && pos[0] <= max[0] && pos[1] >= min[1] && pos[1] <= max[1]
 }
 
 impl<T, I> Map<[I; 2], T>
@@ -224,7 +653,61 @@ where
     I: IntCoord,
 {
     pub fn read<R: std::io::Read>(reader: &mut R) -> MapResult<Self> {
-        let mut data: HashMap<[I; 2], T> = HashMap::new();
+        Self::read_impl(reader, false)
+    }
+
+    /// Read a map, failing on any non-whitespace character that is not a valid tile
+    pub fn read_strict<R: std::io::Read>(reader: &mut R) -> MapResult<Self> {
+        Self::read_impl(reader, true)
+    }
+
+    /// Parse a map from a string like `str::parse`, but fail with `MapError::BadTile` on any
+    /// non-whitespace character that is not a valid tile
+    pub fn parse_strict(s: &str) -> MapResult<Self> {
+        Self::read_strict(&mut s.as_bytes())
+    }
+
+    /// Build a map from a slice of lines
+    pub fn from_lines(lines: &[&str]) -> MapResult<Self> {
+        Self::read(&mut lines.join("\n").as_bytes())
+    }
+
+    /// Read several maps stacked on top of each other, separated by lines equal to `sep`.
+    /// Each map starts at row 0, and blank lines within a map are kept as empty rows.
+    pub fn read_stacked<R: std::io::Read>(reader: &mut R, sep: &str) -> MapResult<Vec<Self>> {
+        let mut sections: Vec<Vec<String>> = vec![Vec::new()];
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line == sep {
+                sections.push(Vec::new());
+            } else {
+                sections
+                    .last_mut()
+                    .expect("there is always a section")
+                    .push(line);
+            }
+        }
+
+        sections
+            .iter()
+            .map(|lines| Self::read(&mut lines.join("\n").as_bytes()))
+            .collect()
+    }
+
+    /// Read a map with the first axis pointing up, so that the last input line ends up at
+    /// row 0 and the first input line at the highest row index
+    pub fn read_y_up<R: std::io::Read>(reader: &mut R) -> MapResult<Self> {
+        let mut input = String::new();
+        reader.read_to_string(&mut input)?;
+
+        let top = I::from_usize(input.lines().count().saturating_sub(1)).unwrap_or_else(I::zero);
+        let map = Self::read(&mut input.as_bytes())?;
+
+        Ok(map.map_keys(|[i, j]| [top - *i, *j]))
+    }
+
+    fn read_impl<R: std::io::Read>(reader: &mut R, strict: bool) -> MapResult<Self> {
+        let mut data: MapData<[I; 2], T> = MapData::default();
 
         let buf_reader = BufReader::new(reader);
         for (i, line) in buf_reader.lines().enumerate() {
@@ -233,6 +716,12 @@ where
                     if let (Some(i), Some(j)) = (I::from_usize(i), I::from_usize(j)) {
                         data.insert([i, j], t);
                     }
+                } else if strict && !c.is_whitespace() {
+                    return Err(MapError::BadTile {
+                        line: i,
+                        col: j,
+                        ch: c,
+                    });
                 }
             }
         }
@@ -248,13 +737,60 @@ impl<T, I> Map<[I; 2], T>
 where
     T: MapTile,
     I: IntCoord,
+{
+    /// Parse a map from a string, using a closure instead of `ParseMapTile` to convert
+    /// characters to tiles. Characters for which `f` returns `None` are left empty.
+    pub fn from_char_grid<F: Fn(char) -> Option<T>>(s: &str, f: F) -> Self {
+        let mut map = Map::new();
+        for (i, line) in s.lines().enumerate() {
+            for (j, c) in line.chars().enumerate() {
+                if let (Some(t), Some(i), Some(j)) = (f(c), I::from_usize(i), I::from_usize(j)) {
+                    map.set([i, j], t);
+                }
+            }
+        }
+
+        map
+    }
+
+    /// Read a map where every tile is encoded by `width` characters, using `f` to convert
+    /// each chunk to a tile. Chunks for which `f` returns `None` are left empty, and a shorter
+    /// chunk at the end of a line is passed on as-is.
+    pub fn read_fixed_width<R, F>(reader: &mut R, width: usize, f: F) -> MapResult<Self>
+    where
+        R: std::io::Read,
+        F: Fn(&str) -> Option<T>,
+    {
+        assert!(width > 0, "Tile width must be positive");
+
+        let mut map = Map::new();
+        for (i, line) in BufReader::new(reader).lines().enumerate() {
+            let chars: Vec<char> = line?.chars().collect();
+            for (j, chunk) in chars.chunks(width).enumerate() {
+                let chunk: String = chunk.iter().collect();
+                if let (Some(t), Some(i), Some(j)) = (f(&chunk), I::from_usize(i), I::from_usize(j))
+                {
+                    map.set([i, j], t);
+                }
+            }
+        }
+
+        Ok(map)
+    }
+}
+
+impl<T, I, S> Map<[I; 2], T, S>
+where
+    T: MapTile,
+    I: IntCoord,
+    S: MapHasher,
 {
     pub fn rotate_right(&self) -> Self {
         let (min, max) = self.get_extent();
         assert_eq!(min[0], I::zero());
         assert_eq!(min[1], I::zero());
 
-        let mut out = Map::new();
+        let mut out = self.empty_like();
 
         //  j 01234    0123
         // i
@@ -276,7 +812,7 @@ where
         assert_eq!(min[0], I::zero());
         assert_eq!(min[1], I::zero());
 
-        let mut out = Map::new();
+        let mut out = self.empty_like();
 
         //  j 01234    0123
         // i
@@ -293,22 +829,35 @@ where
         out
     }
 
-    pub fn flip(&self, axis: usize) -> Self {
-        let (_min, max) = self.get_extent();
-
-        let mut out = Map::new();
-        for (pos, tile) in self.data.iter() {
-            let mut pos = pos.clone();
-            pos[axis] = max[axis] - pos[axis];
+    pub fn rotate_180(&self) -> Self {
+        let (min, max) = self.get_extent();
+        assert_eq!(min[0], I::zero());
+        assert_eq!(min[1], I::zero());
 
-            out.set(pos, tile.clone());
+        let mut out = self.empty_like();
+        for ([i, j], tile) in self.data.iter() {
+            out.set([max[0] - *i, max[1] - *j], tile.clone());
         }
 
         out
     }
 
+    /// Rotate the map by a number of quarter turns, clockwise for positive and
+    /// counter-clockwise for negative numbers. Use `Direction::turn` to rotate headings
+    /// accordingly.
+    pub fn rotate(&self, quarter_turns: i32) -> Self {
+        match quarter_turns.rem_euclid(4) {
+            0 => self.clone(),
+            1 => self.rotate_right(),
+            2 => self.rotate_180(),
+            _ => self.rotate_left(),
+        }
+    }
+
     pub fn to_vecs(&self) -> Vec<Vec<Option<T>>> {
-        let (min, max) = self.get_extent();
+        let Some((min, max)) = self.try_get_extent() else {
+            return Vec::new();
+        };
 
         num::iter::range_inclusive(min[0], max[0])
             .map(|i| {
@@ -318,513 +867,2968 @@ where
             })
             .collect()
     }
-}
 
-impl<T, I> std::fmt::Display for Map<[I; 2], T>
-where
-    T: MapTile,
-    I: IntCoord,
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
-        if self.data.is_empty() {
-            return Ok(());
-        }
+    /// Like `to_vecs`, but filling coordinates without a tile with `default`
+    pub fn to_vecs_or(&self, default: T) -> Vec<Vec<T>> {
+        self.to_vecs()
+            .into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|t| t.unwrap_or_else(|| default.clone()))
+                    .collect()
+            })
+            .collect()
+    }
 
+    /// Count the tiles in each quadrant of the extent, in the order
+    /// top-left, top-right, bottom-left, bottom-right.
+    ///
+    /// For odd dimensions, tiles on the middle row or column are not counted.
+    pub fn quadrant_counts(&self) -> [usize; 4] {
         let (min, max) = self.get_extent();
+        let two = I::one() + I::one();
 
-        for i in num::iter::range_inclusive(min[0], max[0]) {
-            for j in num::iter::range_inclusive(min[1], max[1]) {
-                match self.data.get(&[i, j]) {
-                    Some(t) => t.fmt(f),
-                    None => write!(f, " "),
-                }?;
-            }
-            write!(f, "\n")?;
+        let height = max[0] - min[0] + I::one();
+        let width = max[1] - min[1] + I::one();
+
+        let mut counts = [0; 4];
+        for [i, j] in self.data.keys() {
+            let (di, dj) = (*i - min[0], *j - min[1]);
+
+            let top = if di < height / two {
+                0
+            } else if di >= height - height / two {
+                2
+            } else {
+                continue;
+            };
+
+            let left = if dj < width / two {
+                0
+            } else if dj >= width - width / two {
+                1
+            } else {
+                continue;
+            };
+
+            counts[top + left] += 1;
         }
 
-        Ok(())
+        counts
     }
-}
 
-impl<T, I> std::str::FromStr for Map<[I; 2], T>
-where
-    T: ParseMapTile,
-    I: IntCoord,
-{
-    type Err = MapError;
+    /// Trace a beam entering `start` heading into `dir`, returning all coordinates it passes.
+    ///
+    /// For every tile the beam enters, `interact` returns the directions it leaves the tile in,
+    /// so mirrors deflect the beam and splitters return several directions. Missing tiles let
+    /// the beam pass straight through. The beam stops when leaving the extent, and every
+    /// (coordinate, direction) state is only traced once so loops terminate.
+    pub fn trace_beam<F>(&self, start: [I; 2], dir: Direction, interact: F) -> HashSet<[I; 2]>
+    where
+        F: Fn(&T, Direction) -> Vec<Direction>,
+    {
+        let extent = self.get_extent();
 
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Map::read(&mut s.as_bytes())
-    }
-}
+        let mut seen: HashSet<([I; 2], Direction)> = HashSet::new();
+        let mut queue = vec![(start, dir)];
 
-////// Code for 3D maps
+        while let Some((pos, dir)) = queue.pop() {
+            if !in_extent_2d(&pos, &extent) || !seen.insert((pos, dir)) {
+                continue;
+            }
 
-impl<I> MapCoordinate for [I; 3]
-where
-    I: IntCoord,
-{
-    type ExtentIter = Extent3DIterator<I>;
+            let out_dirs = match self.data.get(&pos) {
+                Some(tile) => interact(tile, dir),
+                None => vec![dir],
+            };
 
-    fn elementwise_min(a: Self, b: Self) -> Self {
-        [
-            std::cmp::min(a[0], b[0]),
-            std::cmp::min(a[1], b[1]),
-            std::cmp::min(a[2], b[2]),
-        ]
+            for out_dir in out_dirs {
+                if let Some(next) = out_dir.try_step(pos) {
+                    queue.push((next, out_dir));
+                }
+            }
+        }
+
+        seen.into_iter().map(|(pos, _)| pos).collect()
     }
 
-    fn elementwise_max(a: Self, b: Self) -> Self {
-        [
-            std::cmp::max(a[0], b[0]),
-            std::cmp::max(a[1], b[1]),
-            std::cmp::max(a[2], b[2]),
-        ]
+    /// Breadth-first search from `start` over orthogonally adjacent tiles, returning the
+    /// distance to every reachable coordinate. Missing tiles are not passable.
+    pub fn bfs<P: Fn(&T) -> bool>(&self, start: [I; 2], passable: P) -> HashMap<[I; 2], usize> {
+        self.bfs_impl(start, passable).0
     }
 
-    fn extent_iterator(min: Self, max: Self) -> Self::ExtentIter {
-        Extent3DIterator {
-            min,
-            max,
-            current: Some(min.clone()),
+    /// Breadth-first search from `start` over orthogonally adjacent tiles, returning the
+    /// predecessor of every reached coordinate other than `start`. Use `reconstruct` to
+    /// rebuild a path from it.
+    pub fn bfs_tree<P: Fn(&T) -> bool>(
+        &self,
+        start: [I; 2],
+        passable: P,
+    ) -> HashMap<[I; 2], [I; 2]> {
+        self.bfs_impl(start, passable).1
+    }
+
+    fn bfs_impl<P: Fn(&T) -> bool>(&self, start: [I; 2], passable: P) -> BfsResult<[I; 2]> {
+        let mut dists = HashMap::new();
+        let mut preds = HashMap::new();
+
+        let mut queue = VecDeque::new();
+        dists.insert(start, 0);
+        queue.push_back((start, 0));
+
+        while let Some((pos, dist)) = queue.pop_front() {
+            for next in neighbors_2d(pos) {
+                if dists.contains_key(&next) || !self.data.get(&next).is_some_and(&passable) {
+                    continue;
+                }
+
+                dists.insert(next, dist + 1);
+                preds.insert(next, pos);
+                queue.push_back((next, dist + 1));
+            }
         }
+
+        (dists, preds)
     }
-}
 
-pub struct Extent3DIterator<I>
-where
-    I: IntCoord,
-{
-    min: [I; 3],
-    max: [I; 3],
-    current: Option<[I; 3]>,
-}
+    /// Count the coordinates that can be reached in exactly `steps` steps from `start`, which
+    /// are those at a distance of at most `steps` with the same parity, since any surplus
+    /// steps can be spent stepping back and forth
+    pub fn reachable_in<P: Fn(&T) -> bool>(
+        &self,
+        start: [I; 2],
+        steps: usize,
+        passable: P,
+    ) -> usize {
+        self.bfs(start, passable)
+            .values()
+            .filter(|d| **d <= steps && **d % 2 == steps % 2)
+            .count()
+    }
 
-impl<I> Iterator for Extent3DIterator<I>
-where
-    I: IntCoord,
-{
-    type Item = [I; 3];
+    /// Find the length of the shortest walk from `start` that visits all `waypoints` in any
+    /// order, optionally returning to `start` at the end. Returns `None` if some waypoint
+    /// cannot be reached.
+    ///
+    /// Distances between waypoints are found by BFS, and the visiting order is optimized by
+    /// dynamic programming over subsets, so this is only suitable for a few waypoints.
+    pub fn tour<P: Fn(&T) -> bool>(
+        &self,
+        start: [I; 2],
+        waypoints: &[[I; 2]],
+        passable: P,
+        return_to_start: bool,
+    ) -> Option<usize> {
+        let n = waypoints.len();
+
+        // dists[i][j] between points, where point n is the start
+        let points: Vec<[I; 2]> = waypoints.iter().copied().chain(Some(start)).collect();
+        let dists = points
+            .iter()
+            .map(|from| {
+                let reached = self.bfs(*from, &passable);
+                points
+                    .iter()
+                    .map(|to| reached.get(to).copied())
+                    .collect::<Option<Vec<usize>>>()
+            })
+            .collect::<Option<Vec<Vec<usize>>>>()?;
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(c) = self.current {
-            let mut next = c.clone();
+        // best[mask][last] is the shortest walk from start visiting the waypoints in mask and
+        // ending at waypoint last
+        let mut best = vec![vec![usize::MAX; n]; 1 << n];
+        for (last, row) in dists[n].iter().take(n).enumerate() {
+            best[1 << last][last] = *row;
+        }
+
+        for mask in 1..(1usize << n) {
+            for last in 0..n {
+                let cost = best[mask][last];
+                if cost == usize::MAX {
+                    continue;
+                }
+
+                for next in (0..n).filter(|next| mask & (1 << next) == 0) {
+                    let next_mask = mask | (1 << next);
+                    let next_cost = cost + dists[last][next];
+                    if next_cost < best[next_mask][next] {
+                        best[next_mask][next] = next_cost;
+                    }
+                }
+            }
+        }
+
+        if n == 0 {
+            return Some(0);
+        }
+
+        (0..n)
+            .map(|last| {
+                let back = if return_to_start { dists[last][n] } else { 0 };
+                best[(1 << n) - 1][last] + back
+            })
+            .min()
+    }
+
+    /// Breadth-first search on an infinite plane tiled with copies of the map, returning the
+    /// distance to every coordinate reachable within `steps` steps.
+    ///
+    /// Tiles are looked up modulo the extent, while the returned coordinates are unbounded.
+    /// Missing tiles are not passable.
+    pub fn bfs_infinite<P: Fn(&T) -> bool>(
+        &self,
+        start: [I; 2],
+        steps: usize,
+        passable: P,
+    ) -> HashMap<[i64; 2], usize> {
+        let (min, max) = self.get_extent();
+        let to_i64 = |v: I| v.to_i64().expect("coordinate fits into i64");
+        let min = [to_i64(min[0]), to_i64(min[1])];
+        let size = [to_i64(max[0]) - min[0] + 1, to_i64(max[1]) - min[1] + 1];
+
+        let tile_at = |pos: [i64; 2]| {
+            let i = I::from_i64((pos[0] - min[0]).rem_euclid(size[0]) + min[0])?;
+            let j = I::from_i64((pos[1] - min[1]).rem_euclid(size[1]) + min[1])?;
+            self.data.get(&[i, j])
+        };
 
-            next[0] = next[0] + I::one();
-            if next[0] > self.max[0] {
-                next[0] = self.min[0];
-                next[1] = next[1] + I::one();
+        let start = [to_i64(start[0]), to_i64(start[1])];
+        let mut dists = HashMap::new();
+        let mut queue = VecDeque::new();
+        dists.insert(start, 0);
+        queue.push_back((start, 0));
+
+        while let Some((pos, dist)) = queue.pop_front() {
+            if dist >= steps {
+                continue;
             }
 
-            if next[1] > self.max[1] {
-                next[1] = self.min[1];
-                next[2] = next[2] + I::one();
+            for dir in Direction::iter() {
+                let [di, dj] = dir.dpos();
+                let next = [pos[0] + di as i64, pos[1] + dj as i64];
+
+                if dists.contains_key(&next) || !tile_at(next).is_some_and(&passable) {
+                    continue;
+                }
+
+                dists.insert(next, dist + 1);
+                queue.push_back((next, dist + 1));
             }
+        }
 
-            if next[2] > self.max[2] {
-                self.current = None
-            } else {
-                self.current = Some(next)
+        dists
+    }
+
+    /// Find the cheapest cost of reaching every coordinate from `start` using Dijkstra's
+    /// algorithm, where `cost` gives the cost of entering a tile or `None` if it is
+    /// impassable. Missing tiles are impassable.
+    pub fn bfs_weighted<F>(&self, start: [I; 2], cost: F) -> HashMap<[I; 2], usize>
+    where
+        F: Fn(&[I; 2], &T) -> Option<usize>,
+    {
+        let mut dists = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        queue.push(Reverse((0, start)));
+
+        while let Some(Reverse((dist, pos))) = queue.pop() {
+            if dists.contains_key(&pos) {
+                continue;
             }
+            dists.insert(pos, dist);
 
-            Some(c)
-        } else {
-            None
+            for next in neighbors_2d(pos) {
+                if dists.contains_key(&next) {
+                    continue;
+                }
+
+                if let Some(c) = self.data.get(&next).and_then(|t| cost(&next, t)) {
+                    queue.push(Reverse((dist + c, next)));
+                }
+            }
         }
+
+        dists
     }
-}
 
-impl<T, I> Map<[I; 3], T>
-where
-    T: MapTile,
-    I: IntCoord,
-{
-    /// Convert a 2D map to a single-layered 3D map
-    pub fn from_2d(map: &Map<[I; 2], T>) -> Self {
-        let data: HashMap<[I; 3], T> = map
-            .data
-            .iter()
-            .map(|(key, tile)| {
-                let key = [I::zero(), key[0], key[1]];
-                (key, tile.clone())
-            })
-            .collect();
+    /// Find the first tile in direction `dir` from `from` (exclusive) that satisfies `stop`,
+    /// staying within the extent
+    pub fn cast<P: Fn(&T) -> bool>(
+        &self,
+        from: [I; 2],
+        dir: Direction,
+        stop: P,
+    ) -> Option<([I; 2], &T)> {
+        let extent = self.get_extent();
+
+        let mut pos = dir.try_step(from)?;
+        while in_extent_2d(&pos, &extent) {
+            if let Some(tile) = self.data.get(&pos) {
+                if stop(tile) {
+                    return Some((pos, tile));
+                }
+            }
 
-        Map {
-            data,
-            fixed_extent: None,
+            pos = dir.try_step(pos)?;
         }
+
+        None
     }
 
-    /// Slice a 3D map into a 2D map along one dimension
-    pub fn slice(&self, i: I, axis: usize) -> Map<[I; 2], T> {
-        let (ax0, ax1) = match axis {
-            0 => (1, 2),
-            1 => (0, 2),
-            2 => (0, 1),
-            _ => panic!("Invalid axis: {}", axis),
-        };
+    /// Iterate over the cells in direction `dir` from `from` (exclusive) along with their
+    /// tiles, if any, until leaving the extent
+    pub fn ray(&self, from: [I; 2], dir: Direction) -> impl Iterator<Item = ([I; 2], Option<&T>)> {
+        let extent = self.get_extent();
 
-        let data: HashMap<[I; 2], T> = self
-            .data
-            .iter()
-            .filter_map(|(k, t)| {
-                if k[axis] == i {
-                    Some(([k[ax0], k[ax1]], t.clone()))
-                } else {
-                    None
+        std::iter::successors(dir.try_step(from), move |pos| dir.try_step(*pos))
+            .take_while(move |pos| in_extent_2d(pos, &extent))
+            .map(move |pos| (pos, self.data.get(&pos)))
+    }
+
+    /// Slide every movable tile into direction `dir` as far as possible.
+    ///
+    /// Tiles stop at the edge of the extent, at blocking tiles and at other movable tiles.
+    /// Any other tiles (e.g. explicit empty floor tiles) swap places with the moving tile.
+    pub fn tilt<M, B>(&mut self, dir: Direction, movable: M, blocking: B)
+    where
+        M: Fn(&T) -> bool,
+        B: Fn(&T) -> bool,
+    {
+        let extent = self.get_extent();
+
+        // move tiles closest to the edge first so that they can block the others
+        let mut to_move = self.find_all_where(|_, t| movable(t));
+        to_move.sort_by(|a, b| match dir {
+            Direction::North => a[0].cmp(&b[0]),
+            Direction::South => b[0].cmp(&a[0]),
+            Direction::West => a[1].cmp(&b[1]),
+            Direction::East => b[1].cmp(&a[1]),
+        });
+
+        for mut pos in to_move {
+            while let Some(next) = dir.try_step(pos) {
+                if !in_extent_2d(&next, &extent) {
+                    break;
                 }
-            })
-            .collect();
 
-        let fixed_extent = self
-            .fixed_extent
-            .map(|(min, max)| ([min[ax0], min[ax1]], [max[ax0], max[ax1]]));
+                if self
+                    .data
+                    .get(&next)
+                    .is_some_and(|t| movable(t) || blocking(t))
+                {
+                    break;
+                }
 
-        Map { data, fixed_extent }
+                let tile = self.data.remove(&pos).expect("moving tile exists");
+                if let Some(displaced) = self.data.insert(next, tile) {
+                    self.data.insert(pos, displaced);
+                }
+
+                pos = next;
+            }
+        }
     }
 
-    pub fn to_vecs(&self) -> Vec<Vec<Vec<Option<T>>>> {
+    /// Repeat the map `times` in each axis, passing the tile offset `[ti, tj]` of each copy
+    /// to `transform` to derive the tiles of the copy
+    pub fn tile<F>(&self, times: [I; 2], transform: F) -> Self
+    where
+        F: Fn(&T, [I; 2]) -> T,
+    {
         let (min, max) = self.get_extent();
+        let size = [max[0] - min[0] + I::one(), max[1] - min[1] + I::one()];
+
+        let mut out = self.empty_like();
+        for ti in num::iter::range(I::zero(), times[0]) {
+            for tj in num::iter::range(I::zero(), times[1]) {
+                for ([i, j], tile) in self.data.iter() {
+                    let pos = [*i + ti * size[0], *j + tj * size[1]];
+                    out.set(pos, transform(tile, [ti, tj]));
+                }
+            }
+        }
 
-        num::iter::range_inclusive(min[0], max[0])
-            .map(|i| {
-                num::iter::range_inclusive(min[1], max[1])
-                    .map(|j| {
-                        num::iter::range_inclusive(min[2], max[2])
-                            .map(|k| self.data.get(&[i, j, k]).cloned())
-                            .collect()
-                    })
-                    .collect()
-            })
-            .collect()
+        out.fixed_extent = self.fixed_extent.map(|(min, _)| {
+            let max = [
+                min[0] + times[0] * size[0] - I::one(),
+                min[1] + times[1] * size[1] - I::one(),
+            ];
+            (min, max)
+        });
+
+        out
     }
-}
 
-impl<T, I> std::fmt::Display for Map<[I; 3], T>
-where
-    T: MapTile,
-    I: IntCoord,
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
+    /// Iterate over the coordinates on the outer ring of the extent, clockwise starting from
+    /// the top-left corner
+    pub fn edge_coords(&self) -> impl Iterator<Item = [I; 2]> {
+        let (min, max) = self.get_extent();
+
+        let mut out: Vec<[I; 2]> = num::iter::range_inclusive(min[1], max[1])
+            .map(|j| [min[0], j])
+            .collect();
+
+        out.extend(num::iter::range_inclusive(min[0] + I::one(), max[0]).map(|i| [i, max[1]]));
+
+        if max[0] > min[0] && max[1] > min[1] {
+            let mut bottom: Vec<[I; 2]> = num::iter::range(min[1], max[1])
+                .map(|j| [max[0], j])
+                .collect();
+            bottom.reverse();
+            out.extend(bottom);
+
+            let mut left: Vec<[I; 2]> = num::iter::range(min[0] + I::one(), max[0])
+                .map(|i| [i, min[1]])
+                .collect();
+            left.reverse();
+            out.extend(left);
+        }
+
+        out.into_iter()
+    }
+
+    /// Get the present tiles on the outer ring of the extent with their values, clockwise
+    /// starting from the top-left corner like `edge_coords`
+    pub fn border_tiles(&self) -> Vec<([I; 2], &T)> {
         if self.data.is_empty() {
-            return Ok(());
+            return Vec::new();
+        }
+
+        self.edge_coords()
+            .filter_map(|pos| self.get(&pos).map(|tile| (pos, tile)))
+            .collect()
+    }
+
+    /// Add `fill` tiles at all empty coordinates within `radius` of an existing tile, as
+    /// measured by `metric`
+    pub fn dilate(&self, radius: usize, metric: Metric, fill: T) -> Self {
+        let r = radius as i64;
+        let offsets: Vec<[i64; 2]> = (-r..=r)
+            .flat_map(|di| (-r..=r).map(move |dj| [di, dj]))
+            .filter(|d| metric.norm(*d) <= radius as u64)
+            .collect();
+
+        let mut out = self.clone();
+        for pos in self.data.keys() {
+            for d in offsets.iter() {
+                if let Some(p) = checked_offset(*pos, *d) {
+                    out.data.entry(p).or_insert_with(|| fill.clone());
+                }
+            }
         }
 
+        out
+    }
+
+    /// Cyclically shift the tiles of `row` by `by` columns towards higher column indices,
+    /// wrapping around within the extent. Negative shifts move towards lower indices.
+    pub fn cycle_row(&mut self, row: I, by: I) {
         let (min, max) = self.get_extent();
+        self.cycle_line(1, row, by, min[1], max[1]);
+    }
 
-        for i in num::iter::range_inclusive(min[0], max[0]) {
-            write!(f, "Layer {} =========\n{}\n", i, self.slice(i, 0))?;
+    /// Cyclically shift the tiles of `col` by `by` rows towards higher row indices, wrapping
+    /// around within the extent. Negative shifts move towards lower indices.
+    pub fn cycle_col(&mut self, col: I, by: I) {
+        let (min, max) = self.get_extent();
+        self.cycle_line(0, col, by, min[0], max[0]);
+    }
+
+    /// Cyclically shift the tiles along `axis` that are at `line` on the other axis, within
+    /// `lo..=hi`
+    fn cycle_line(&mut self, axis: usize, line: I, by: I, lo: I, hi: I) {
+        let len = hi - lo + I::one();
+        let mut shift = by % len;
+        if shift < I::zero() {
+            shift = shift + len;
         }
 
-        Ok(())
+        let at = |k: I| {
+            let mut pos = [line, line];
+            pos[axis] = k;
+            pos
+        };
+
+        let tiles: Vec<(I, T)> = num::iter::range_inclusive(lo, hi)
+            .filter_map(|k| self.data.remove(&at(k)).map(|t| (k, t)))
+            .collect();
+
+        for (k, tile) in tiles {
+            self.set(at(lo + (k - lo + shift) % len), tile);
+        }
     }
-}
 
-////// Code for 4D maps
+    /// Remove outer rings of the extent for as long as they consist entirely of `border`
+    /// tiles. Coordinates of the remaining tiles are kept as-is.
+    pub fn strip_border(&self, border: &T) -> Self
+    where
+        T: PartialEq,
+    {
+        let (mut min, mut max) = self.get_extent();
 
-impl<I> MapCoordinate for [I; 4]
-where
-    I: IntCoord,
-{
-    type ExtentIter = Extent4DIterator<I>;
+        while min[0] <= max[0] && min[1] <= max[1] {
+            let on_ring =
+                |p: &[I; 2]| p[0] == min[0] || p[0] == max[0] || p[1] == min[1] || p[1] == max[1];
+            let uniform = <[I; 2]>::extent_iterator(min, max)
+                .filter(on_ring)
+                .all(|p| self.get(&p) == Some(border));
 
-    fn elementwise_min(a: Self, b: Self) -> Self {
-        [
-            std::cmp::min(a[0], b[0]),
-            std::cmp::min(a[1], b[1]),
-            std::cmp::min(a[2], b[2]),
-            std::cmp::min(a[3], b[3]),
-        ]
+            if !uniform {
+                break;
+            }
+
+            let (Some(i), Some(j)) = (max[0].checked_sub(&I::one()), max[1].checked_sub(&I::one()))
+            else {
+                // everything was stripped on an unsigned map
+                return self.empty_like();
+            };
+
+            min = [min[0] + I::one(), min[1] + I::one()];
+            max = [i, j];
+        }
+
+        let mut out = self.empty_like();
+        for (pos, tile) in self.data.iter() {
+            if in_extent_2d(pos, &(min, max)) {
+                out.set(*pos, tile.clone());
+            }
+        }
+
+        out
+    }
+
+    /// Group the tiles for which `connect` holds into orthogonally connected components,
+    /// ordered by their top-left-most coordinate
+    pub fn connected_components<P: Fn(&T) -> bool>(&self, connect: P) -> Vec<HashSet<[I; 2]>> {
+        let mut starts: Vec<[I; 2]> = self
+            .data
+            .iter()
+            .filter(|(_, t)| connect(t))
+            .map(|(p, _)| *p)
+            .collect();
+        starts.sort();
+
+        let mut seen: HashSet<[I; 2]> = HashSet::new();
+        let mut out = Vec::new();
+
+        for start in starts {
+            if seen.contains(&start) {
+                continue;
+            }
+
+            let component: HashSet<[I; 2]> = self.bfs(start, &connect).into_keys().collect();
+            seen.extend(component.iter().copied());
+            out.push(component);
+        }
+
+        out
+    }
+
+    /// Follow a loop of connected tiles (e.g. pipes) from `start` back to itself, returning
+    /// the loop cells in order starting with `start`.
+    ///
+    /// `connections` lists the directions a tile connects to, and two neighboring tiles are
+    /// connected only if both connect to each other. If the start tile has no connections
+    /// (e.g. an unknown start marker), any connected neighbor may continue the loop.
+    pub fn trace_loop<F>(&self, start: [I; 2], connections: F) -> Option<Vec<[I; 2]>>
+    where
+        F: Fn(&T) -> Vec<Direction>,
+    {
+        let start_connections = connections(self.get(&start)?);
+        let connects_back = |pos: &[I; 2], dir: Direction| {
+            if *pos == start {
+                return start_connections.is_empty() || start_connections.contains(&dir.reverse());
+            }
+
+            self.get(pos)
+                .is_some_and(|t| connections(t).contains(&dir.reverse()))
+        };
+
+        for first in Direction::iter() {
+            if !start_connections.is_empty() && !start_connections.contains(&first) {
+                continue;
+            }
+
+            let mut path = vec![start];
+            let mut dir = first;
+            let mut pos = start;
+
+            while let Some(next) = dir.try_step(pos).filter(|n| connects_back(n, dir)) {
+                if next == start {
+                    if path.len() > 2 {
+                        return Some(path);
+                    }
+                    break;
+                }
+
+                let out = connections(self.get(&next)?)
+                    .into_iter()
+                    .find(|d| *d != dir.reverse());
+
+                match out {
+                    Some(out) if path.len() <= self.data.len() => {
+                        path.push(next);
+                        pos = next;
+                        dir = out;
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Find all offsets at which every tile of `pattern` lies on a tile of the map for which
+    /// `matches(map_tile, pattern_tile)` holds, in reading order. Missing pattern tiles match
+    /// anything.
+    pub fn find_pattern<F>(&self, pattern: &Map<[I; 2], T>, matches: F) -> Vec<[I; 2]>
+    where
+        F: Fn(&T, &T) -> bool,
+    {
+        let Some(anchor) = pattern.data.keys().min().copied() else {
+            return Vec::new();
+        };
+
+        let mut out: Vec<[I; 2]> = self
+            .data
+            .keys()
+            .filter_map(|q| Some([q[0].checked_sub(&anchor[0])?, q[1].checked_sub(&anchor[1])?]))
+            .filter(|o| {
+                pattern.data.iter().all(|(p, pt)| {
+                    let (Some(i), Some(j)) = (o[0].checked_add(&p[0]), o[1].checked_add(&p[1]))
+                    else {
+                        return false;
+                    };
+
+                    self.get(&[i, j]).is_some_and(|t| matches(t, pt))
+                })
+            })
+            .collect();
+
+        out.sort();
+        out
+    }
+
+    /// Get the coordinates of the neighbors of `coord` whose tiles satisfy `pred`, including
+    /// diagonal neighbors if `diagonal` is set
+    pub fn neighbors_where<P: Fn(&T) -> bool>(
+        &self,
+        coord: [I; 2],
+        diagonal: bool,
+        pred: P,
+    ) -> Vec<[I; 2]> {
+        let candidates: Vec<[I; 2]> = if diagonal {
+            neighbors8_2d(coord).collect()
+        } else {
+            neighbors_2d(coord).collect()
+        };
+
+        candidates
+            .into_iter()
+            .filter(|n| self.data.get(n).is_some_and(&pred))
+            .collect()
+    }
+
+    /// Iterate over the orthogonal neighbors of `coord` that have a tile
+    pub fn present_neighbors4(&self, coord: [I; 2]) -> impl Iterator<Item = ([I; 2], &T)> {
+        neighbors_2d(coord).filter_map(move |n| self.data.get(&n).map(|t| (n, t)))
+    }
+
+    /// Iterate over the tiles within the extent in reading order, visiting only present tiles
+    /// so that huge sparse extents stay cheap
+    pub fn iter_present_in_extent(&self) -> impl Iterator<Item = ([I; 2], &T)> {
+        let extent = self.try_get_extent();
+
+        let mut tiles: Vec<([I; 2], &T)> = self
+            .data
+            .iter()
+            .filter(|(p, _)| extent.is_some_and(|e| in_extent_2d(p, &e)))
+            .map(|(p, t)| (*p, t))
+            .collect();
+        tiles.sort_by_key(|(p, _)| *p);
+
+        tiles.into_iter()
+    }
+
+    /// Iterate over all tiles together with the tiles around them, in arbitrary order.
+    ///
+    /// Neighbors are listed in the order North, East, South, West, or row by row from the
+    /// top-left if `diagonal` is set. Missing tiles are reported as `None`.
+    pub fn iter_with_neighbors(
+        &self,
+        diagonal: bool,
+    ) -> impl Iterator<Item = ([I; 2], &T, Vec<Option<&T>>)> {
+        let offsets: Vec<[i64; 2]> = if diagonal {
+            (-1..=1)
+                .flat_map(|di| (-1..=1).map(move |dj| [di, dj]))
+                .filter(|d| *d != [0, 0])
+                .collect()
+        } else {
+            Direction::iter()
+                .map(|d| {
+                    let [di, dj] = d.dpos();
+                    [di as i64, dj as i64]
+                })
+                .collect()
+        };
+
+        self.data.iter().map(move |(pos, tile)| {
+            let neighbors = offsets
+                .iter()
+                .map(|d| checked_offset(*pos, *d).and_then(|p| self.data.get(&p)))
+                .collect();
+
+            (*pos, tile, neighbors)
+        })
+    }
+
+    /// Count the corners of a region, which equals the number of straight sides of its
+    /// outline. Both convex and concave corners are counted.
+    pub fn region_corners(&self, region: &HashSet<[I; 2]>) -> usize {
+        let inside =
+            |p: [I; 2], d: [i64; 2]| checked_offset(p, d).is_some_and(|q| region.contains(&q));
+
+        let mut corners = 0;
+        for pos in region.iter() {
+            for dir in Direction::iter() {
+                let [ai, aj] = dir.dpos();
+                let [bi, bj] = dir.rot_right().dpos();
+
+                let a = inside(*pos, [ai as i64, aj as i64]);
+                let b = inside(*pos, [bi as i64, bj as i64]);
+                let diagonal = inside(*pos, [(ai + bi) as i64, (aj + bj) as i64]);
+
+                if (!a && !b) || (a && b && !diagonal) {
+                    corners += 1;
+                }
+            }
+        }
+
+        corners
+    }
+
+    /// Get the Manhattan distance from every tile to the nearest obstacle tile within the
+    /// extent, using a breadth-first search starting at all obstacles at once. Obstacles have
+    /// a distance of 0, and the result is empty if there are no obstacles.
+    pub fn distance_transform<P: Fn(&T) -> bool>(&self, is_obstacle: P) -> Map<[I; 2], usize> {
+        let extent = self.get_extent();
+
+        let mut dists: HashMap<[I; 2], usize> = HashMap::new();
+        let mut queue = VecDeque::new();
+        for (pos, tile) in self.data.iter() {
+            if is_obstacle(tile) {
+                dists.insert(*pos, 0);
+                queue.push_back(*pos);
+            }
+        }
+
+        while let Some(pos) = queue.pop_front() {
+            let dist = dists[&pos];
+            for next in neighbors_2d(pos) {
+                if !in_extent_2d(&next, &extent) || dists.contains_key(&next) {
+                    continue;
+                }
+
+                dists.insert(next, dist + 1);
+                queue.push_back(next);
+            }
+        }
+
+        let mut out = Map::new();
+        for pos in self.data.keys() {
+            if let Some(d) = dists.get(pos) {
+                out.set(*pos, *d);
+            }
+        }
+
+        out
+    }
+
+    /// Get the extent of the connected region of tiles for which `connect` holds around
+    /// `start`, or `None` if there is no such tile at `start`
+    pub fn flood_extent<P: Fn(&T) -> bool>(
+        &self,
+        start: [I; 2],
+        connect: P,
+    ) -> Option<([I; 2], [I; 2])> {
+        if !self.get(&start).is_some_and(&connect) {
+            return None;
+        }
+
+        Some(<[I; 2]>::get_extent(self.bfs(start, connect).into_keys()))
+    }
+
+    /// Get the connected region of tiles for which `connect` holds around `start`, together
+    /// with a histogram of the values of the tiles orthogonally bordering the region. Each
+    /// bordering tile is counted once, even if it touches the region on several sides.
+    pub fn region_with_border_counts<P: Fn(&T) -> bool>(
+        &self,
+        start: [I; 2],
+        connect: P,
+    ) -> (HashSet<[I; 2]>, HashMap<T, usize>)
+    where
+        T: Eq + std::hash::Hash,
+    {
+        if !self.get(&start).is_some_and(&connect) {
+            return (HashSet::new(), HashMap::new());
+        }
+
+        let region: HashSet<[I; 2]> = self.bfs(start, connect).into_keys().collect();
+
+        let border: HashSet<[I; 2]> = region
+            .iter()
+            .flat_map(|pos| neighbors_2d(*pos))
+            .filter(|pos| !region.contains(pos))
+            .collect();
+
+        let mut counts: HashMap<T, usize> = HashMap::new();
+        for tile in border.iter().filter_map(|pos| self.get(pos)) {
+            *counts.entry(tile.clone()).or_default() += 1;
+        }
+
+        (region, counts)
+    }
+
+    /// Get the largest connected component of tiles for which `connect` holds. On ties, the
+    /// component containing the top-left-most coordinate wins.
+    pub fn largest_component<P: Fn(&T) -> bool>(&self, connect: P) -> Option<HashSet<[I; 2]>> {
+        self.connected_components(connect).into_iter().fold(
+            None,
+            |best: Option<HashSet<[I; 2]>>, c| match best {
+                Some(b) if b.len() >= c.len() => Some(b),
+                _ => Some(c),
+            },
+        )
+    }
+
+    /// Get the connected components of tiles for which `connect` holds that do not touch the
+    /// boundary of the extent, i.e. fully enclosed pockets
+    pub fn enclosed_components<P: Fn(&T) -> bool>(&self, connect: P) -> Vec<HashSet<[I; 2]>> {
+        let (min, max) = self.get_extent();
+        let on_border =
+            |p: &[I; 2]| p[0] == min[0] || p[0] == max[0] || p[1] == min[1] || p[1] == max[1];
+
+        self.connected_components(connect)
+            .into_iter()
+            .filter(|component| !component.iter().any(on_border))
+            .collect()
+    }
+}
+
+impl<I> Map<[I; 2], char>
+where
+    I: IntCoord,
+{
+    /// Parse a map that stores every character as itself, leaving `empty` characters absent
+    pub fn from_dense(s: &str, empty: char) -> Self {
+        Map::from_char_grid(s, |c| if c == empty { None } else { Some(c) })
+    }
+}
+
+impl<I, S> Map<[I; 2], char, S>
+where
+    I: IntCoord,
+    S: MapHasher,
+{
+    /// Render each row of the extent as a string, writing `hole` for absent tiles
+    pub fn to_strings(&self, hole: char) -> Vec<String> {
+        self.to_vecs_or(hole)
+            .into_iter()
+            .map(|row| row.into_iter().collect())
+            .collect()
+    }
+}
+
+impl<I, S> Map<[I; 2], u8, S>
+where
+    I: IntCoord,
+    S: MapHasher,
+{
+    /// Look along the four directions from every tile of a height map, returning the set of
+    /// tiles visible from outside the extent and a map of scenic scores.
+    ///
+    /// A tile is visible if all tiles towards an edge are strictly lower. Its scenic score is
+    /// the product of its viewing distances, i.e. the number of tiles seen in each direction
+    /// up to and including the first tile that is at least as high.
+    pub fn visibility(&self) -> (HashSet<[I; 2]>, Map<[I; 2], usize>) {
+        let extent = self.get_extent();
+
+        let mut visible = HashSet::new();
+        let mut scenic = Map::new();
+
+        for (pos, height) in self.data.iter() {
+            let mut score = 1;
+
+            for dir in Direction::iter() {
+                let mut distance = 0;
+                let mut blocked = false;
+
+                let mut current = dir.try_step(*pos);
+                while let Some(p) = current.filter(|p| in_extent_2d(p, &extent)) {
+                    distance += 1;
+
+                    if self.data.get(&p).is_some_and(|h| h >= height) {
+                        blocked = true;
+                        break;
+                    }
+
+                    current = dir.try_step(p);
+                }
+
+                if !blocked {
+                    visible.insert(*pos);
+                }
+
+                score *= distance;
+            }
+
+            scenic.set(*pos, score);
+        }
+
+        (visible, scenic)
+    }
+
+    /// Get the coordinates of tiles that are strictly lower than all of their orthogonal
+    /// neighbors in reading order. Missing neighbors do not count.
+    pub fn local_minima(&self) -> Vec<[I; 2]> {
+        self.local_extrema(|tile, neighbor| tile < neighbor)
+    }
+
+    /// Get the coordinates of tiles that are strictly higher than all of their orthogonal
+    /// neighbors in reading order. Missing neighbors do not count.
+    pub fn local_maxima(&self) -> Vec<[I; 2]> {
+        self.local_extrema(|tile, neighbor| tile > neighbor)
+    }
+
+    fn local_extrema<F: Fn(u8, u8) -> bool>(&self, beats: F) -> Vec<[I; 2]> {
+        let mut out: Vec<[I; 2]> = self
+            .data
+            .iter()
+            .filter(|(pos, tile)| {
+                self.present_neighbors4(**pos)
+                    .all(|(_, neighbor)| beats(**tile, *neighbor))
+            })
+            .map(|(pos, _)| *pos)
+            .collect();
+
+        out.sort();
+        out
+    }
+
+    /// Run one generation of an energy-cascade automaton, returning the number of flashes.
+    ///
+    /// Every tile gains one energy. Tiles above 9 flash, giving one energy to all of their
+    /// 8-neighbors, which may flash in turn. Every tile flashes at most once per generation
+    /// and flashed tiles are reset to 0.
+    pub fn flash_step(&mut self) -> usize {
+        let mut queue = Vec::new();
+        for (pos, energy) in self.data.iter_mut() {
+            *energy = energy.saturating_add(1);
+            if *energy > 9 {
+                queue.push(*pos);
+            }
+        }
+
+        let mut flashed: HashSet<[I; 2]> = queue.iter().copied().collect();
+        while let Some(pos) = queue.pop() {
+            for n in neighbors8_2d(pos) {
+                if let Some(energy) = self.data.get_mut(&n) {
+                    *energy = energy.saturating_add(1);
+                    if *energy > 9 && flashed.insert(n) {
+                        queue.push(n);
+                    }
+                }
+            }
+        }
+
+        for pos in flashed.iter() {
+            self.data.insert(*pos, 0);
+        }
+
+        flashed.len()
+    }
+}
+
+impl<I, S> Map<[I; 2], i64, S>
+where
+    I: IntCoord,
+    S: MapHasher,
+{
+    /// Combine two maps tile by tile over the union of their coordinates, treating missing
+    /// tiles as 0
+    fn combine(&self, other: &Self, f: impl Fn(i64, i64) -> i64) -> Self {
+        let mut out = self.empty_like();
+        for pos in self.data.keys().chain(other.data.keys()) {
+            let a = self.get(pos).copied().unwrap_or(0);
+            let b = other.get(pos).copied().unwrap_or(0);
+            out.set(*pos, f(a, b));
+        }
+        out
+    }
+
+    /// Add two maps elementwise, treating missing tiles as 0
+    pub fn add(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a + b)
+    }
+
+    /// Subtract `other` from this map elementwise, treating missing tiles as 0
+    pub fn sub(&self, other: &Self) -> Self {
+        self.combine(other, |a, b| a - b)
+    }
+}
+
+impl<I, S> Map<[I; 2], bool, S>
+where
+    I: IntCoord,
+    S: MapHasher,
+{
+    /// Render the map as braille characters, packing blocks of 4 rows by 2 columns into a
+    /// single character. Missing tiles are rendered as unset dots.
+    pub fn to_braille(&self) -> String {
+        if self.data.is_empty() {
+            return String::new();
+        }
+
+        // bit offsets of the braille dots, indexed by [row][column] within a block
+        const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+        let (min, max) = self.get_extent();
+        let four = I::from_usize(4).expect("4 fits into coordinate");
+        let two = I::one() + I::one();
+
+        let mut out = String::new();
+        for bi in num::iter::range_step_inclusive(min[0], max[0], four) {
+            for bj in num::iter::range_step_inclusive(min[1], max[1], two) {
+                let mut code = 0x2800;
+                for (di, row) in DOTS.iter().enumerate() {
+                    for (dj, dot) in row.iter().enumerate() {
+                        let pos = [
+                            bi + I::from_usize(di).expect("offset fits into coordinate"),
+                            bj + I::from_usize(dj).expect("offset fits into coordinate"),
+                        ];
+
+                        if self.data.get(&pos).copied().unwrap_or(false) {
+                            code |= dot;
+                        }
+                    }
+                }
+
+                out.push(std::char::from_u32(code).expect("valid braille code point"));
+            }
+            out.push('\n');
+        }
+
+        out
+    }
+
+    /// Encode the extent row by row as runs of equal values, treating missing tiles as `false`
+    pub fn rle(&self) -> Vec<(bool, usize)> {
+        let Some((min, max)) = self.try_get_extent() else {
+            return Vec::new();
+        };
+
+        let mut runs: Vec<(bool, usize)> = Vec::new();
+        for pos in row_major(min, max) {
+            let value = self.data.get(&pos).copied().unwrap_or(false);
+            match runs.last_mut() {
+                Some((last, count)) if *last == value => *count += 1,
+                _ => runs.push((value, 1)),
+            }
+        }
+
+        runs
+    }
+}
+
+impl<I> Map<[I; 2], bool>
+where
+    I: IntCoord,
+{
+    /// Decode runs produced by `rle` into a map with a tile at every coordinate of `extent`.
+    /// Runs longer than the extent are truncated.
+    pub fn from_rle(runs: &[(bool, usize)], (min, max): ([I; 2], [I; 2])) -> Self {
+        let values = runs
+            .iter()
+            .flat_map(|&(value, count)| std::iter::repeat_n(value, count));
+
+        let mut map = Map::new();
+        for (pos, value) in row_major(min, max).zip(values) {
+            map.set(pos, value);
+        }
+        map
+    }
+}
+
+/// A 2D map together with a passability predicate, for walking mazes without threading the
+/// predicate through every call
+pub struct Walkable<'a, I: IntCoord, T: MapTile> {
+    map: &'a Map<[I; 2], T>,
+    passable: Box<dyn Fn(&T) -> bool + 'a>,
+}
+
+impl<'a, I: IntCoord, T: MapTile> Walkable<'a, I, T> {
+    pub fn new<P: Fn(&T) -> bool + 'a>(map: &'a Map<[I; 2], T>, passable: P) -> Self {
+        Walkable {
+            map,
+            passable: Box::new(passable),
+        }
+    }
+
+    /// Get the tile at `coord` if it is present and passable
+    pub fn get(&self, coord: &[I; 2]) -> Option<&'a T> {
+        self.map.get(coord).filter(|t| (self.passable)(t))
+    }
+
+    /// Iterate over the passable 4-neighbors of `coord`
+    pub fn neighbors(&self, coord: [I; 2]) -> impl Iterator<Item = [I; 2]> + '_ {
+        neighbors_2d(coord).filter(move |n| self.get(n).is_some())
+    }
+}
+
+/// Assert that two maps are equal, panicking with both maps rendered along with their extents
+/// otherwise
+#[cfg(any(test, feature = "test-utils"))]
+#[track_caller]
+pub fn assert_map_eq<I, T>(a: &Map<[I; 2], T>, b: &Map<[I; 2], T>)
+where
+    I: IntCoord,
+    T: MapTile + PartialEq,
+{
+    if a != b {
+        panic!(
+            "Map mismatch:\na (extent {:?}):\n{}\nb (extent {:?}):\n{}",
+            a.get_extent(),
+            a,
+            b.get_extent(),
+            b
+        )
+    }
+}
+
+impl<T, I, S> std::fmt::Display for Map<[I; 2], T, S>
+where
+    T: MapTile,
+    I: IntCoord,
+    S: MapHasher,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
+        let Some((min, max)) = self.try_get_extent() else {
+            return Ok(());
+        };
+
+        for i in num::iter::range_inclusive(min[0], max[0]) {
+            for j in num::iter::range_inclusive(min[1], max[1]) {
+                match self.data.get(&[i, j]) {
+                    Some(t) => t.fmt(f),
+                    None => write!(f, " "),
+                }?;
+            }
+            write!(f, "\n")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<T, I> std::str::FromStr for Map<[I; 2], T>
+where
+    T: ParseMapTile,
+    I: IntCoord,
+{
+    type Err = MapError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Map::read(&mut s.as_bytes())
+    }
+}
+
+////// Code for 3D maps
+
+impl<I> MapCoordinate for [I; 3]
+where
+    I: IntCoord,
+{
+    type ExtentIter = Extent3DIterator<I>;
+
+    fn elementwise_min(a: Self, b: Self) -> Self {
+        [
+            std::cmp::min(a[0], b[0]),
+            std::cmp::min(a[1], b[1]),
+            std::cmp::min(a[2], b[2]),
+        ]
+    }
+
+    fn elementwise_max(a: Self, b: Self) -> Self {
+        [
+            std::cmp::max(a[0], b[0]),
+            std::cmp::max(a[1], b[1]),
+            std::cmp::max(a[2], b[2]),
+        ]
+    }
+
+    fn extent_iterator(min: Self, max: Self) -> Self::ExtentIter {
+        Extent3DIterator {
+            min,
+            max,
+            current: extent_start(min, max),
+        }
+    }
+}
+
+pub struct Extent3DIterator<I>
+where
+    I: IntCoord,
+{
+    min: [I; 3],
+    max: [I; 3],
+    current: Option<[I; 3]>,
+}
+
+impl<I> Iterator for Extent3DIterator<I>
+where
+    I: IntCoord,
+{
+    type Item = [I; 3];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.current?;
+        self.current = advance_extent(c, &self.min, &self.max);
+        Some(c)
+    }
+}
+
+impl<T, I> Map<[I; 3], T>
+where
+    T: MapTile,
+    I: IntCoord,
+{
+    /// Convert a 2D map to a single-layered 3D map
+    pub fn from_2d(map: &Map<[I; 2], T>) -> Self {
+        let data: MapData<[I; 3], T> = map
+            .data
+            .iter()
+            .map(|(key, tile)| {
+                let key = [I::zero(), key[0], key[1]];
+                (key, tile.clone())
+            })
+            .collect();
+
+        Map {
+            data,
+            fixed_extent: None,
+        }
+    }
+}
+
+impl<T, I, S> Map<[I; 3], T, S>
+where
+    T: MapTile,
+    I: IntCoord,
+    S: MapHasher,
+{
+    /// Slice a 3D map into a 2D map along one dimension
+    pub fn slice(&self, i: I, axis: usize) -> Map<[I; 2], T> {
+        let (ax0, ax1) = match axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            2 => (0, 1),
+            _ => panic!("Invalid axis: {}", axis),
+        };
+
+        let data: MapData<[I; 2], T> = self
+            .data
+            .iter()
+            .filter_map(|(k, t)| {
+                if k[axis] == i {
+                    Some(([k[ax0], k[ax1]], t.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let fixed_extent = self
+            .fixed_extent
+            .map(|(min, max)| ([min[ax0], min[ax1]], [max[ax0], max[ax1]]));
+
+        Map { data, fixed_extent }
+    }
+
+    /// Rotate the map around the origin by a number of quarter turns around an axis,
+    /// counter-clockwise when looking from the positive end of the axis towards the origin
+    pub fn rotate_axis(&self, axis: usize, quarter_turns: i32) -> Self
+    where
+        I: num::Signed,
+    {
+        assert!(axis < 3, "Bad axis for 3D rotation: {}", axis);
+
+        let mut out = self.empty_like();
+        for (pos, tile) in self.data.iter() {
+            let mut pos = *pos;
+            for _ in 0..quarter_turns.rem_euclid(4) {
+                pos = crate::math::quarter_turn_3d(pos, axis);
+            }
+
+            out.set(pos, tile.clone());
+        }
+
+        out
+    }
+
+    /// Get the map in all 24 orientations of a cube around the origin, starting with the
+    /// unrotated map. Orientations are indexed like `math::rotate_3d`.
+    pub fn orientations_3d(&self) -> Vec<Self>
+    where
+        I: num::Signed,
+    {
+        (0..24)
+            .map(|index| {
+                let mut out = self.empty_like();
+                for (pos, tile) in self.data.iter() {
+                    out.set(crate::math::rotate_3d(*pos, index), tile.clone());
+                }
+                out
+            })
+            .collect()
+    }
+
+    pub fn to_vecs(&self) -> Vec<Vec<Vec<Option<T>>>> {
+        let Some((min, max)) = self.try_get_extent() else {
+            return Vec::new();
+        };
+
+        num::iter::range_inclusive(min[0], max[0])
+            .map(|i| {
+                num::iter::range_inclusive(min[1], max[1])
+                    .map(|j| {
+                        num::iter::range_inclusive(min[2], max[2])
+                            .map(|k| self.data.get(&[i, j, k]).cloned())
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl<T, I, S> std::fmt::Display for Map<[I; 3], T, S>
+where
+    T: MapTile,
+    I: IntCoord,
+    S: MapHasher,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
+        let Some((min, max)) = self.try_get_extent() else {
+            return Ok(());
+        };
+
+        for i in num::iter::range_inclusive(min[0], max[0]) {
+            write!(f, "Layer {} =========\n{}\n", i, self.slice(i, 0))?;
+        }
+
+        Ok(())
+    }
+}
+
+////// Code for 4D maps
+
+impl<I> MapCoordinate for [I; 4]
+where
+    I: IntCoord,
+{
+    type ExtentIter = Extent4DIterator<I>;
+
+    fn elementwise_min(a: Self, b: Self) -> Self {
+        [
+            std::cmp::min(a[0], b[0]),
+            std::cmp::min(a[1], b[1]),
+            std::cmp::min(a[2], b[2]),
+            std::cmp::min(a[3], b[3]),
+        ]
+    }
+
+    fn elementwise_max(a: Self, b: Self) -> Self {
+        [
+            std::cmp::max(a[0], b[0]),
+            std::cmp::max(a[1], b[1]),
+            std::cmp::max(a[2], b[2]),
+            std::cmp::max(a[3], b[3]),
+        ]
+    }
+
+    fn extent_iterator(min: Self, max: Self) -> Self::ExtentIter {
+        Extent4DIterator {
+            min,
+            max,
+            current: extent_start(min, max),
+        }
+    }
+}
+
+pub struct Extent4DIterator<I>
+where
+    I: IntCoord,
+{
+    min: [I; 4],
+    max: [I; 4],
+    current: Option<[I; 4]>,
+}
+
+impl<I> Iterator for Extent4DIterator<I>
+where
+    I: IntCoord,
+{
+    type Item = [I; 4];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let c = self.current?;
+        self.current = advance_extent(c, &self.min, &self.max);
+        Some(c)
+    }
+}
+
+impl<T, I> Map<[I; 4], T>
+where
+    T: MapTile,
+    I: IntCoord,
+{
+    /// Convert a 3D map to a single-layered 4D map
+    pub fn from_3d(map: &Map<[I; 3], T>) -> Self {
+        let data: MapData<[I; 4], T> = map
+            .data
+            .iter()
+            .map(|(key, tile)| {
+                let key = [I::zero(), key[0], key[1], key[2]];
+                (key, tile.clone())
+            })
+            .collect();
+
+        Map {
+            data,
+            fixed_extent: None,
+        }
+    }
+}
+
+impl<T, I, S> Map<[I; 4], T, S>
+where
+    T: MapTile,
+    I: IntCoord,
+    S: MapHasher,
+{
+    /// Slice a 4D map into a 3D map along one dimension
+    pub fn slice(&self, i: I, axis: usize) -> Map<[I; 3], T> {
+        let (ax0, ax1, ax2) = match axis {
+            0 => (1, 2, 3),
+            1 => (0, 2, 3),
+            2 => (0, 1, 3),
+            3 => (0, 1, 2),
+            _ => panic!("Invalid axis: {}", axis),
+        };
+
+        let data: MapData<[I; 3], T> = self
+            .data
+            .iter()
+            .filter_map(|(k, t)| {
+                if k[axis] == i {
+                    Some(([k[ax0], k[ax1], k[ax2]], t.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect();
+
+        let fixed_extent = self.fixed_extent.map(|(min, max)| {
+            (
+                [min[ax0], min[ax1], min[ax2]],
+                [max[ax0], max[ax1], max[ax2]],
+            )
+        });
+
+        Map { data, fixed_extent }
+    }
+
+    pub fn to_vecs(&self) -> Vec<Vec<Vec<Vec<Option<T>>>>> {
+        let Some((min, max)) = self.try_get_extent() else {
+            return Vec::new();
+        };
+
+        num::iter::range_inclusive(min[0], max[0])
+            .map(|i| {
+                num::iter::range_inclusive(min[1], max[1])
+                    .map(|j| {
+                        num::iter::range_inclusive(min[2], max[2])
+                            .map(|k| {
+                                num::iter::range_inclusive(min[3], max[3])
+                                    .map(|l| self.data.get(&[i, j, k, l]).cloned())
+                                    .collect()
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+impl<T, I, S> std::fmt::Display for Map<[I; 4], T, S>
+where
+    T: MapTile,
+    I: IntCoord,
+    S: MapHasher,
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
+        let Some((min, max)) = self.try_get_extent() else {
+            return Ok(());
+        };
+
+        for i in num::iter::range_inclusive(min[0], max[0]) {
+            for j in num::iter::range_inclusive(min[1], max[1]) {
+                write!(
+                    f,
+                    "Layer {}, {} =========\n{}\n",
+                    i,
+                    j,
+                    self.slice(i, 0).slice(j, 0)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    impl ParseMapTile for char {
+        fn from_char(c: char) -> Option<Self> {
+            if c == ' ' {
+                None
+            } else {
+                Some(c)
+            }
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Digit(u8);
+
+    impl std::fmt::Display for Digit {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "{}", self.0)
+        }
+    }
+
+    impl ParseMapTile for Digit {
+        fn from_char(c: char) -> Option<Self> {
+            c.to_digit(10).map(|d| Digit(d as u8))
+        }
+    }
+
+    #[test]
+    fn test_2d_parsing() {
+        let map_string = "ab \nd e";
+        let map = Map::<[usize; 2], char>::read(&mut map_string.as_bytes()).unwrap();
+
+        assert_eq!(map.get_extent(), ([0, 0], [1, 2]));
+
+        assert_eq!(
+            map.to_vecs(),
+            vec![
+                vec![Some('a'), Some('b'), None],
+                vec![Some('d'), None, Some('e')],
+            ]
+        )
+    }
+
+    #[test]
+    fn test_2d_parsing_strict() {
+        let map = Map::<[usize; 2], char>::read_strict(&mut "ab \nd e".as_bytes()).unwrap();
+        assert_eq!(map.data.len(), 4);
+
+        let map = Map::<[usize; 2], Digit>::read_strict(&mut "12\n3x4".as_bytes());
+        match map {
+            Err(MapError::BadTile { line, col, ch }) => assert_eq!((line, col, ch), (1, 1, 'x')),
+            other => panic!("Expected BadTile error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_2d_editing() {
+        let mut map: Map<[usize; 2], char> = Map::new();
+
+        assert_eq!(map.get(&[1, 2]), None);
+        map.set([1, 2], 'a');
+        assert_eq!(map.get(&[1, 2]), Some(&'a'));
+
+        map.set([4, 1], 'c');
+        assert_eq!(map.get_extent(), ([1, 1], [4, 2]));
+
+        map.set([8, 8], 'd');
+        assert_eq!(map.get_extent(), ([1, 1], [8, 8]));
+
+        map.remove(&[8, 8]);
+        assert_eq!(map.get_extent(), ([1, 1], [4, 2]));
+
+        assert_eq!(
+            map.to_vecs(),
+            vec![
+                vec![None, Some('a')],
+                vec![None, None],
+                vec![None, None],
+                vec![Some('c'), None],
+            ]
+        )
+    }
+
+    #[test]
+    fn test_2d_replace() {
+        let mut map: Map<[usize; 2], char> = Map::new();
+        map.set([4, 1], 'c');
+
+        assert_eq!(map.replace([4, 1], 'e'), Some('c'));
+        assert_eq!(map.get(&[4, 1]), Some(&'e'));
+
+        assert_eq!(map.replace([3, 1], 'f'), None);
+        assert_eq!(map.get(&[3, 1]), Some(&'f'));
+        assert_eq!(map.get_extent(), ([3, 1], [4, 1]));
+    }
+
+    #[test]
+    fn test_checked_offset() {
+        assert_eq!(checked_offset([1usize, 2], [-1, 3]), Some([0, 5]));
+        assert_eq!(checked_offset([0usize, 2], [-1, 0]), None);
+        assert_eq!(checked_offset([0i32, 0, 0], [-2, 0, 7]), Some([-2, 0, 7]));
+        assert_eq!(checked_offset([0u8, 0], [0, 300]), None);
+
+        assert_eq!(neighbors8_2d([0usize, 0]).count(), 3);
+        assert_eq!(neighbors_nd([0usize, 0, 0], true).len(), 7);
+        assert_eq!(neighbors_nd([1i32, 1, 1, 1], false).len(), 8);
+    }
+
+    #[test]
+    fn test_2d_display() {
+        let map_string = "ab \nd e";
+        let map = Map::<[usize; 2], char>::read(&mut map_string.as_bytes()).unwrap();
+
+        assert_eq!(format!("{}", map), format!("{}\n", map_string));
+
+        let map2: Map<[usize; 2], char> = Map::new();
+        assert_eq!(format!("{}", map2), "");
+    }
+
+    #[test]
+    fn test_2d_rotating_flipping() {
+        //              R    L
+        //  j 01234    012  012
+        // i
+        // 0  abcde    kfa  ejo
+        // 1  fghIj    Lgb  dIn
+        // 2  kLmno    mhc  chm
+        // 3           nId  bgL
+        // 4           oje  afk
+        //
+        //
+        // FLIP i      FLIP j
+        //
+        //   kLmno     edcba
+        //   fghIj     jIhgf
+        //   abcde     onmLk
+
+        let map = "abcde\nfghIj\nkLmno"
+            .parse::<Map<[usize; 2], char>>()
+            .unwrap();
+
+        assert_map_eq(
+            &map.rotate_right(),
+            &"kfa\nLgb\nmhc\nnId\noje".parse().unwrap(),
+        );
+
+        assert_map_eq(
+            &map.rotate_left(),
+            &"ejo\ndIn\nchm\nbgL\nafk".parse().unwrap(),
+        );
+
+        assert_map_eq(&map.flip(0), &"kLmno\nfghIj\nabcde".parse().unwrap());
+        assert_map_eq(&map.flip(1), &"edcba\njIhgf\nonmLk".parse().unwrap());
+    }
+
+    #[test]
+    fn test_2d_quadrant_counts() {
+        let mut map = "a.b.c\n.....\nd.e.f\n.....\ng.h.i"
+            .parse::<Map<[usize; 2], char>>()
+            .unwrap();
+        map.data.retain(|_, t| *t != '.');
+
+        // middle row and column are excluded
+        assert_eq!(map.quadrant_counts(), [1, 1, 1, 1]);
+
+        map.set([4, 3], 'j');
+        assert_eq!(map.quadrant_counts(), [1, 1, 1, 2]);
+    }
+
+    #[test]
+    fn test_2d_braille() {
+        let mut map: Map<[i32; 2], bool> = Map::new();
+        map.set([0, 0], true);
+        assert_eq!(map.to_braille(), "\u{2801}\n");
+
+        map.set([3, 1], true);
+        assert_eq!(map.to_braille(), "\u{2881}\n");
+
+        map.set([4, 2], true);
+        map.set([4, 0], false);
+        assert_eq!(map.to_braille(), "\u{2881}\u{2800}\n\u{2800}\u{2801}\n");
+
+        assert_eq!(Map::<[i32; 2], bool>::new().to_braille(), "");
+    }
+
+    #[test]
+    fn test_2d_trace_beam() {
+        let map = "....\n.\\..\n...."
+            .parse::<Map<[usize; 2], char>>()
+            .unwrap();
+
+        let mirrors = |t: &char, d: Direction| match (t, d) {
+            ('\\', Direction::East) => vec![Direction::South],
+            ('\\', Direction::South) => vec![Direction::East],
+            ('\\', Direction::West) => vec![Direction::North],
+            ('\\', Direction::North) => vec![Direction::West],
+            _ => vec![d],
+        };
+
+        let straight = map.trace_beam([0, 0], Direction::East, mirrors);
+        assert_eq!(straight, HashSet::from([[0, 0], [0, 1], [0, 2], [0, 3]]));
+
+        let deflected = map.trace_beam([1, 0], Direction::East, mirrors);
+        assert_eq!(deflected, HashSet::from([[1, 0], [1, 1], [2, 1]]));
+    }
+
+    #[test]
+    fn test_2d_edge_coords() {
+        let mut map: Map<[i32; 2], char> = Map::new();
+        map.fixed_extent = Some(([-1, 2], [2, 6]));
+
+        let edge: Vec<[i32; 2]> = map.edge_coords().collect();
+        assert_eq!(edge.len(), 2 * (4 + 5) - 4);
+        assert_eq!(
+            &edge[..6],
+            &[[-1, 2], [-1, 3], [-1, 4], [-1, 5], [-1, 6], [0, 6]]
+        );
+        assert_eq!(edge.last(), Some(&[0, 2]));
+        assert_eq!(edge.iter().collect::<HashSet<_>>().len(), edge.len());
+
+        map.fixed_extent = Some(([0, 0], [0, 3]));
+        assert_eq!(map.edge_coords().count(), 4);
+
+        map.fixed_extent = Some(([0, 0], [2, 0]));
+        assert_eq!(map.edge_coords().count(), 3);
+    }
+
+    #[test]
+    fn test_map_keys() {
+        let map = "#.O\n.@.".parse::<Map<[usize; 2], char>>().unwrap();
+
+        let wide = map.map_keys(|&[i, j]| [i, 2 * j]);
+        assert_eq!(wide.get(&[0, 4]), Some(&'O'));
+        assert_eq!(wide.get(&[1, 2]), Some(&'@'));
+        assert_eq!(wide.get(&[0, 1]), None);
+        assert_eq!(wide.data.len(), map.data.len());
+
+        assert!(map.map_keys_checked(|&[i, j]| [i, 2 * j]).is_some());
+        assert!(map.map_keys_checked(|&[i, _j]| [i, 0]).is_none());
+    }
+
+    #[test]
+    fn test_map_data_hasher() {
+        // results must not depend on the hasher the map was created with
+        fn check<S: MapHasher>(mut map: Map<[i32; 2], char, S>) {
+            let map_string = "#..#\n.##.\n#..#";
+            for (i, line) in map_string.lines().enumerate() {
+                for (j, c) in line.chars().enumerate() {
+                    map.set([i as i32, j as i32], c);
+                }
+            }
+
+            assert_eq!(map.data.len(), 12);
+            let mut walls = map.find_all(&'#');
+            walls.sort();
+            assert_eq!(walls, vec![[0, 0], [0, 3], [1, 1], [1, 2], [2, 0], [2, 3]]);
+            assert_eq!(format!("{}", map), format!("{}\n", map_string));
+        }
+
+        check(Map::new());
+        check(Map::with_hasher(std::hash::BuildHasherDefault::<
+            std::collections::hash_map::DefaultHasher,
+        >::default()));
+        #[cfg(feature = "fxhash")]
+        check(Map::with_fast_hasher());
+    }
+
+    #[test]
+    fn test_2d_bfs_tree() {
+        let map = "S..#\n##.#\n...G\n.#.."
+            .parse::<Map<[usize; 2], char>>()
+            .unwrap();
+        let start = map.find_one(&'S').unwrap();
+        let goal = map.find_one(&'G').unwrap();
+
+        let dists = map.bfs(start, |t| *t != '#');
+        assert_eq!(dists.get(&goal), Some(&5));
+        assert_eq!(dists.get(&[3, 0]), Some(&7));
+        assert_eq!(dists.get(&[0, 3]), None);
+
+        let tree = map.bfs_tree(start, |t| *t != '#');
+        let path = reconstruct(&tree, goal);
+        assert_eq!(path.len(), 6);
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&goal));
+
+        for w in path.windows(2) {
+            let d = w[0][0].abs_diff(w[1][0]) + w[0][1].abs_diff(w[1][1]);
+            assert_eq!(d, 1);
+            assert_ne!(map.get(&w[1]), Some(&'#'));
+        }
+    }
+
+    #[test]
+    fn test_2d_cast() {
+        let map = "@.. .#.#\n#......."
+            .parse::<Map<[usize; 2], char>>()
+            .unwrap();
+
+        assert_eq!(
+            map.cast([0, 0], Direction::East, |t| *t == '#'),
+            Some(([0, 5], &'#'))
+        );
+        assert_eq!(
+            map.cast([0, 5], Direction::East, |t| *t == '#'),
+            Some(([0, 7], &'#'))
+        );
+        assert_eq!(map.cast([0, 7], Direction::East, |t| *t == '#'), None);
+        assert_eq!(map.cast([0, 0], Direction::North, |t| *t == '#'), None);
+        assert_eq!(
+            map.cast([0, 0], Direction::South, |t| *t == '#'),
+            Some(([1, 0], &'#'))
+        );
+    }
+
+    #[test]
+    fn test_2d_tilt() {
+        let mut map = "O.O.\n.O.#\nO..O\n#O.O"
+            .parse::<Map<[usize; 2], char>>()
+            .unwrap();
+
+        map.tilt(Direction::North, |t| *t == 'O', |t| *t == '#');
+        assert_map_eq(&map, &"OOO.\nOO.#\n...O\n#..O".parse().unwrap());
+
+        map.tilt(Direction::East, |t| *t == 'O', |t| *t == '#');
+        assert_map_eq(&map, &".OOO\n.OO#\n...O\n#..O".parse().unwrap());
+
+        // a single column of rocks against a fixed obstacle
+        let mut column = ".\n#\n.\nO\n.\nO".parse::<Map<[usize; 2], char>>().unwrap();
+        column.tilt(Direction::North, |t| *t == 'O', |t| *t == '#');
+        assert_map_eq(&column, &".\n#\nO\nO\n.\n.".parse().unwrap());
+    }
+
+    #[test]
+    fn test_signature() {
+        let tiles = [([0, 0], 'a'), ([3, 1], 'b'), ([2, 5], 'c'), ([1, 1], 'd')];
+
+        let mut a: Map<[i32; 2], char> = Map::new();
+        for (pos, t) in tiles.iter() {
+            a.set(*pos, *t);
+        }
+
+        let mut b: Map<[i32; 2], char> = Map::new();
+        for (pos, t) in tiles.iter().rev() {
+            b.set(*pos, *t);
+        }
+
+        assert_eq!(a.signature(), b.signature());
+
+        b.set([1, 1], 'e');
+        assert_ne!(a.signature(), b.signature());
+    }
+
+    #[test]
+    fn test_3d_4d_flip() {
+        let mut map: Map<[i32; 3], char> = Map::new();
+        map.set([0, 1, 0], 'a');
+        map.set([1, 3, 2], 'b');
+        map.set([0, 2, 1], 'c');
+
+        let flipped = map.flip(1);
+        assert_eq!(flipped.get_extent(), map.get_extent());
+        assert_eq!(flipped.get(&[0, 3, 0]), Some(&'a'));
+        assert_eq!(flipped.get(&[1, 1, 2]), Some(&'b'));
+        assert_eq!(flipped.get(&[0, 2, 1]), Some(&'c'));
+        assert_eq!(flipped.flip(1), map);
+
+        let map4 = Map::from_3d(&map);
+        let flipped4 = map4.flip(3);
+        assert_eq!(flipped4.get(&[0, 0, 1, 2]), Some(&'a'));
+        assert_eq!(flipped4.get(&[0, 1, 3, 0]), Some(&'b'));
+    }
+
+    #[test]
+    fn test_2d_flip_extent() {
+        // flipping keeps the extent, even close to the end of the coordinate range
+        let mut map: Map<[u8; 2], char> = Map::new();
+        map.set([250, 3], 'a');
+        map.set([255, 5], 'b');
+
+        let flipped = map.flip(0);
+        assert_eq!(flipped.get_extent(), map.get_extent());
+        assert_eq!(flipped.get(&[255, 3]), Some(&'a'));
+        assert_eq!(flipped.get(&[250, 5]), Some(&'b'));
+
+        let flipped = map.flip(1);
+        assert_eq!(flipped.get(&[250, 5]), Some(&'a'));
+        assert_eq!(flipped.get(&[255, 3]), Some(&'b'));
+    }
+
+    #[test]
+    fn test_2d_from_char_grid() {
+        let map: Map<[usize; 2], u32> = Map::from_char_grid("1.3\n.5.", |c| c.to_digit(10));
+
+        assert_eq!(map.data.len(), 3);
+        assert_eq!(
+            map.to_vecs(),
+            vec![vec![Some(1), None, Some(3)], vec![None, Some(5), None]]
+        );
+    }
+
+    #[test]
+    fn test_2d_bfs_weighted() {
+        let map: Map<[usize; 2], u32> = Map::from_char_grid("1911\n1919\n1111", |c| c.to_digit(10));
+
+        let dists = map.bfs_weighted([0, 0], |_, t| Some(*t as usize));
+        assert_eq!(dists.get(&[0, 0]), Some(&0));
+        assert_eq!(dists.get(&[0, 3]), Some(&7));
+        assert_eq!(dists.get(&[1, 3]), Some(&14));
+
+        let dists = map.bfs_weighted([0, 0], |_, t| if *t < 9 { Some(1) } else { None });
+        assert_eq!(dists.get(&[0, 3]), Some(&7));
+        assert_eq!(dists.get(&[0, 1]), None);
+    }
+
+    #[test]
+    fn test_2d_tile() {
+        let mut map: Map<[usize; 2], u32> = Map::new();
+        map.set([0, 0], 1);
+
+        let tiled = map.tile([2, 2], |v, [ti, tj]| v + (ti + tj) as u32);
+        assert_eq!(
+            tiled.to_vecs(),
+            vec![vec![Some(1), Some(2)], vec![Some(2), Some(3)]]
+        );
+
+        let map: Map<[usize; 2], u32> = Map::from_char_grid("12\n34", |c| c.to_digit(10));
+        let tiled = map.tile([1, 3], |v, [_, tj]| v * 10_u32.pow(tj as u32));
+        assert_eq!(tiled.get_extent(), ([0, 0], [1, 5]));
+        assert_eq!(tiled.get(&[1, 5]), Some(&400));
+    }
+
+    #[test]
+    fn test_2d_from_dense() {
+        let map: Map<[usize; 2], char> = Map::from_dense("a.b\n.c.", '.');
+
+        assert_eq!(map.data.len(), 3);
+        assert_eq!(map.get(&[0, 0]), Some(&'a'));
+        assert_eq!(map.get(&[0, 2]), Some(&'b'));
+        assert_eq!(map.get(&[1, 1]), Some(&'c'));
+        assert_eq!(map.get(&[0, 1]), None);
+    }
+
+    #[test]
+    fn test_2d_bfs_infinite() {
+        let map = ".....\n.##..\n..S#.\n.#...\n....#"
+            .parse::<Map<[usize; 2], char>>()
+            .unwrap();
+        let start = map.find_one(&'S').unwrap();
+
+        let steps = 6;
+        let infinite = map.bfs_infinite(start, steps, |t| *t != '#');
+
+        // brute force: explicitly tile the map so that the search cannot leave it
+        let tiled = map.tile([3, 3], |t, [ti, tj]| {
+            if *t == 'S' && (ti, tj) != (1, 1) {
+                '.'
+            } else {
+                *t
+            }
+        });
+        let brute: HashMap<[i64; 2], usize> = tiled
+            .bfs(tiled.find_one(&'S').unwrap(), |t| *t != '#')
+            .into_iter()
+            .filter(|(_, d)| *d <= steps)
+            .map(|([i, j], d)| ([i as i64 - 5, j as i64 - 5], d))
+            .collect();
+
+        assert_eq!(infinite, brute);
+
+        for parity in 0..2 {
+            let count =
+                |d: &HashMap<[i64; 2], usize>| d.values().filter(|v| *v % 2 == parity).count();
+            assert_eq!(count(&infinite), count(&brute));
+        }
+
+        assert!(infinite.keys().any(|[i, _]| *i < 0));
+    }
+
+    #[test]
+    fn test_2d_visibility() {
+        let map: Map<[usize; 2], u8> =
+            Map::from_char_grid("30373\n25512\n65332\n33549\n35390", |c| {
+                c.to_digit(10).map(|d| d as u8)
+            });
+
+        let (visible, scenic) = map.visibility();
+        assert_eq!(visible.len(), 21);
+        assert!(visible.contains(&[1, 1]));
+        assert!(!visible.contains(&[1, 3]));
+
+        assert_eq!(scenic.get(&[1, 2]), Some(&4));
+        assert_eq!(scenic.get(&[3, 2]), Some(&8));
+        assert_eq!(scenic.data.values().max(), Some(&8));
+    }
+
+    #[test]
+    fn test_2d_walkable() {
+        let map: Map<[i32; 2], char> = "#####\n#..##\n#.#.#\n#####".parse().unwrap();
+        let walkable = Walkable::new(&map, |&c| c == '.');
+
+        assert_eq!(walkable.get(&[1, 1]), Some(&'.'));
+        assert_eq!(walkable.get(&[0, 0]), None);
+        assert_eq!(walkable.get(&[10, 10]), None);
+
+        let neighbors: HashSet<[i32; 2]> = walkable.neighbors([1, 1]).collect();
+        assert_eq!(neighbors, HashSet::from([[1, 2], [2, 1]]));
+
+        assert_eq!(walkable.neighbors([2, 3]).count(), 0);
+    }
+
+    #[test]
+    fn test_2d_components() {
+        let map: Map<[i32; 2], char> = "######\n#..#..\n#..###\n######".parse().unwrap();
+
+        let components = map.connected_components(|&c| c == '.');
+        assert_eq!(components.len(), 2);
+        assert_eq!(
+            components[0],
+            HashSet::from([[1, 1], [1, 2], [2, 1], [2, 2]])
+        );
+        assert_eq!(components[1], HashSet::from([[1, 4], [1, 5]]));
+
+        let enclosed = map.enclosed_components(|&c| c == '.');
+        assert_eq!(enclosed, vec![components[0].clone()]);
+
+        assert_eq!(map.connected_components(|&c| c == '#').len(), 1);
+        assert!(map.enclosed_components(|&c| c == '#').is_empty());
+    }
+
+    #[test]
+    fn test_for_each_mut() {
+        let mut map: Map<[i32; 2], Digit> = "123\n4 6".parse().unwrap();
+
+        let mut visited = Vec::new();
+        map.for_each_mut(|pos, Digit(d)| {
+            visited.push(*pos);
+            *d += 1;
+        });
+
+        visited.sort();
+        assert_eq!(visited, vec![[0, 0], [0, 1], [0, 2], [1, 0], [1, 2]]);
+        assert_eq!(map.to_string(), "234\n5 7\n");
+    }
+
+    #[test]
+    fn test_2d_flash_step() {
+        let mut map: Map<[usize; 2], u8> =
+            Map::from_char_grid("11111\n19991\n19191\n19991\n11111", |c| {
+                c.to_digit(10).map(|d| d as u8)
+            });
+
+        assert_eq!(map.flash_step(), 9);
+        assert_eq!(map.to_string(), "34543\n40004\n50005\n40004\n34543\n");
+
+        assert_eq!(map.flash_step(), 0);
+        assert_eq!(map.to_string(), "45654\n51115\n61116\n51115\n45654\n");
+    }
+
+    #[test]
+    fn test_2d_largest_component() {
+        let map: Map<[i32; 2], char> = "aa.bb\na..bb\n....b".parse().unwrap();
+
+        let largest = map.largest_component(|&c| c != '.').unwrap();
+        assert_eq!(
+            largest,
+            HashSet::from([[0, 3], [0, 4], [1, 3], [1, 4], [2, 4]])
+        );
+
+        // ties are broken towards the top-left
+        let tied: Map<[i32; 2], char> = "a.b\na.b".parse().unwrap();
+        assert_eq!(
+            tied.largest_component(|&c| c != '.'),
+            Some(HashSet::from([[0, 0], [1, 0]]))
+        );
+
+        assert_eq!(map.largest_component(|&c| c == '#'), None);
+    }
+
+    #[test]
+    fn test_extent_iterators() {
+        let single: Vec<[i32; 2]> = MapCoordinate::extent_iterator([3, -4], [3, -4]).collect();
+        assert_eq!(single, vec![[3, -4]]);
+
+        let negative: Vec<[i32; 2]> = MapCoordinate::extent_iterator([-2, -2], [-1, -1]).collect();
+        assert_eq!(negative, vec![[-2, -2], [-1, -2], [-2, -1], [-1, -1]]);
+
+        let empty: Vec<[i32; 2]> = MapCoordinate::extent_iterator([0, 0], [-1, 5]).collect();
+        assert!(empty.is_empty());
+
+        let edge: Vec<[u8; 2]> = MapCoordinate::extent_iterator([254, 255], [255, 255]).collect();
+        assert_eq!(edge, vec![[254, 255], [255, 255]]);
+
+        let single: Vec<[i64; 3]> =
+            MapCoordinate::extent_iterator([-1, 0, 1], [-1, 0, 1]).collect();
+        assert_eq!(single, vec![[-1, 0, 1]]);
+
+        let negative: Vec<[i64; 3]> =
+            MapCoordinate::extent_iterator([-2, -2, -2], [-1, -1, -1]).collect();
+        assert_eq!(negative.len(), 8);
+        assert_eq!(negative.first(), Some(&[-2, -2, -2]));
+        assert_eq!(negative[1], [-1, -2, -2]);
+        assert_eq!(negative.last(), Some(&[-1, -1, -1]));
+
+        let empty: Vec<[i64; 3]> = MapCoordinate::extent_iterator([0, 0, 1], [0, 0, 0]).collect();
+        assert!(empty.is_empty());
+
+        let hyper: Vec<[i8; 4]> =
+            MapCoordinate::extent_iterator([-1, -1, -1, -1], [0, 0, 0, 0]).collect();
+        assert_eq!(hyper.len(), 16);
+    }
+
+    #[test]
+    fn test_2d_read_fixed_width() {
+        let input = "##..AB\n..CD##\n";
+        let map: Map<[i32; 2], String> = Map::read_fixed_width(&mut input.as_bytes(), 2, |s| {
+            if s == ".." {
+                None
+            } else {
+                Some(s.to_string())
+            }
+        })
+        .unwrap();
+
+        assert_eq!(map.get_extent(), ([0, 0], [1, 2]));
+        assert_eq!(map.get(&[0, 0]).map(|s| s.as_str()), Some("##"));
+        assert_eq!(map.get(&[0, 1]), None);
+        assert_eq!(map.get(&[0, 2]).map(|s| s.as_str()), Some("AB"));
+        assert_eq!(map.get(&[1, 1]).map(|s| s.as_str()), Some("CD"));
+        assert_eq!(map.data.len(), 4);
+    }
+
+    #[test]
+    fn test_2d_strip_border() {
+        let map: Map<[i32; 2], char> = "#####\n#.#.#\n#...#\n#####".parse().unwrap();
+
+        let inner = map.strip_border(&'#');
+        assert_eq!(inner.get_extent(), ([1, 1], [2, 3]));
+        assert_eq!(inner.to_string(), ".#.\n...\n");
+
+        // rings are stripped repeatedly, but only while they are uniform
+        let nested: Map<[i32; 2], char> = "#####\n#####\n##.##\n#####\n#####".parse().unwrap();
+        let stripped = nested.strip_border(&'#');
+        assert_eq!(stripped.data.len(), 1);
+        assert_eq!(stripped.get(&[2, 2]), Some(&'.'));
+
+        let open: Map<[i32; 2], char> = "#.#\n#.#".parse().unwrap();
+        assert_eq!(open.strip_border(&'#'), open);
+
+        let walls: Map<[usize; 2], char> = "##\n##".parse().unwrap();
+        assert!(walls.strip_border(&'#').data.is_empty());
+    }
+
+    #[test]
+    fn test_tile_set() {
+        let map: Map<[i32; 2], char> = "#.#\n.a.\n#.#".parse().unwrap();
+        assert_eq!(map.tile_set(), HashSet::from(['#', '.', 'a']));
+
+        assert!(Map::<[i32; 2], char>::new().tile_set().is_empty());
+    }
+
+    #[test]
+    fn test_2d_assemble() {
+        let mut placements: HashMap<[usize; 2], Map<[usize; 2], char>> = HashMap::new();
+        placements.insert([0, 0], "###\n#a#\n###".parse().unwrap());
+        placements.insert([0, 1], "###\n#b#\n###".parse().unwrap());
+        placements.insert([1, 0], "###\n#c#\n###".parse().unwrap());
+        placements.insert([1, 1], "###\n#d#\n###".parse().unwrap());
+
+        let joined = assemble(&placements, [3, 3], 1);
+        assert_eq!(joined.get_extent(), ([0, 0], [1, 1]));
+        assert_eq!(joined.to_string(), "ab\ncd\n");
+
+        let uncut = assemble(&placements, [3, 3], 0);
+        assert_eq!(uncut.get_extent(), ([0, 0], [5, 5]));
+        assert_eq!(uncut.get(&[4, 4]), Some(&'d'));
+    }
+
+    #[test]
+    fn test_2d_dilate() {
+        let mut map: Map<[i32; 2], char> = Map::new();
+        map.set([0, 0], '#');
+
+        let square = map.dilate(1, Metric::Chebyshev, 'o');
+        assert_eq!(square.get_extent(), ([-1, -1], [1, 1]));
+        assert_eq!(square.to_string(), "ooo\no#o\nooo\n");
+
+        let diamond = map.dilate(2, Metric::Manhattan, 'o');
+        assert_eq!(diamond.data.len(), 13);
+        assert_eq!(diamond.get(&[1, 1]), Some(&'o'));
+        assert_eq!(diamond.get(&[2, 1]), None);
+
+        // dilating is clipped at the edge of unsigned coordinates
+        let corner: Map<[usize; 2], char> = "#".parse().unwrap();
+        assert_eq!(corner.dilate(1, Metric::Chebyshev, 'o').data.len(), 4);
+    }
+
+    #[test]
+    fn test_2d_flood_extent() {
+        let map: Map<[i32; 2], char> = "#....\n#..#.\n####.\n.....".parse().unwrap();
+
+        // L-shaped wall region
+        assert_eq!(
+            map.flood_extent([0, 0], |&c| c == '#'),
+            Some(([0, 0], [2, 3]))
+        );
+        assert_eq!(
+            map.flood_extent([0, 1], |&c| c == '.'),
+            Some(([0, 0], [3, 4]))
+        );
+
+        assert_eq!(map.flood_extent([0, 1], |&c| c == '#'), None);
+        assert_eq!(map.flood_extent([9, 9], |&c| c == '#'), None);
+    }
+
+    #[test]
+    fn test_empty_extent() {
+        let mut map: Map<[i32; 2], char> = Map::new();
+        assert_eq!(map.try_get_extent(), None);
+        assert_eq!(map.to_string(), "");
+        assert!(map.to_vecs().is_empty());
+
+        map.set([1, 2], 'x');
+        assert_eq!(map.try_get_extent(), Some(([1, 2], [1, 2])));
+        assert_eq!(map.to_string(), "x\n");
+
+        map.remove(&[1, 2]);
+        assert_eq!(map.try_get_extent(), None);
+
+        let map3: Map<[i32; 3], char> = Map::new();
+        assert_eq!(map3.try_get_extent(), None);
+        assert_eq!(map3.to_string(), "");
+        assert!(map3.to_vecs().is_empty());
+
+        let map4: Map<[i32; 4], char> = Map::new();
+        assert_eq!(map4.to_string(), "");
+        assert!(map4.to_vecs().is_empty());
+    }
+
+    #[test]
+    fn test_2d_rotate() {
+        let map: Map<[i32; 2], char> = "ab\ncd\nef".parse().unwrap();
+
+        assert_eq!(map.rotate_180().to_string(), "fe\ndc\nba\n");
+        assert_eq!(map.rotate(2), map.rotate_180());
+        assert_eq!(map.rotate(-2), map.rotate_180());
+        assert_eq!(map.rotate(1), map.rotate_right());
+        assert_eq!(map.rotate(-1), map.rotate_left());
+        assert_eq!(map.rotate(-3), map.rotate_right());
+        assert_eq!(map.rotate(4), map);
+
+        // a heading rotates along with the map
+        let rotated = map.rotate(1);
+        let a = rotated.find_one(&'a').unwrap();
+        let b = rotated.find_one(&'b').unwrap();
+        assert_eq!(
+            Direction::from_delta([b[0] - a[0], b[1] - a[1]]),
+            Some(Direction::East.turn(1))
+        );
+    }
+
+    #[test]
+    fn test_2d_distance_transform() {
+        let map: Map<[i32; 2], char> = ".....\n.....\n..#..\n.....".parse().unwrap();
+
+        let dists = map.distance_transform(|&c| c == '#');
+        assert_eq!(dists.to_string(), "43234\n32123\n21012\n32123\n");
+
+        let walls: Map<[i32; 2], char> = "#...#".parse().unwrap();
+        let dists = walls.distance_transform(|&c| c == '#');
+        assert_eq!(dists.to_string(), "01210\n");
+
+        assert!(walls.distance_transform(|&c| c == 'x').data.is_empty());
+    }
+
+    #[test]
+    fn test_2d_iter_with_neighbors() {
+        let map: Map<[usize; 2], char> = "abc\ndef\nghi".parse().unwrap();
+
+        let neighbors: HashMap<[usize; 2], (char, Vec<Option<char>>)> = map
+            .iter_with_neighbors(false)
+            .map(|(p, t, n)| (p, (*t, n.into_iter().map(|t| t.copied()).collect())))
+            .collect();
+
+        assert_eq!(neighbors.len(), 9);
+        assert_eq!(
+            neighbors[&[1, 1]],
+            ('e', vec![Some('b'), Some('f'), Some('h'), Some('d')])
+        );
+        assert_eq!(
+            neighbors[&[0, 0]],
+            ('a', vec![None, Some('b'), Some('d'), None])
+        );
+
+        let (_, _, center) = map
+            .iter_with_neighbors(true)
+            .find(|(p, _, _)| *p == [1, 1])
+            .unwrap();
+        let center: String = center.into_iter().map(|t| *t.unwrap()).collect();
+        assert_eq!(center, "abcdfghi");
+
+        let (_, _, corner) = map
+            .iter_with_neighbors(true)
+            .find(|(p, _, _)| *p == [2, 2])
+            .unwrap();
+        assert_eq!(
+            corner,
+            vec![
+                Some(&'e'),
+                Some(&'f'),
+                None,
+                Some(&'h'),
+                None,
+                None,
+                None,
+                None
+            ]
+        );
+    }
+
+    #[test]
+    fn test_symmetric_difference() {
+        let a: Map<[i32; 2], char> = "ab \n cd".parse().unwrap();
+        let b: Map<[i32; 2], Digit> = "1 2\n 3 4".parse().unwrap();
+
+        let (only_a, only_b) = a.symmetric_difference(&b);
+        assert_eq!(only_a, vec![[0, 1], [1, 2]]);
+        assert_eq!(only_b, vec![[0, 2], [1, 3]]);
+
+        assert_eq!(a.symmetric_difference(&a), (vec![], vec![]));
+    }
+
+    #[test]
+    fn test_spiral() {
+        let first: Vec<[i64; 2]> = spiral().take(12).collect();
+        assert_eq!(
+            first,
+            vec![
+                [0, 0],
+                [0, 1],
+                [-1, 1],
+                [-1, 0],
+                [-1, -1],
+                [0, -1],
+                [1, -1],
+                [1, 0],
+                [1, 1],
+                [1, 2],
+                [0, 2],
+                [-1, 2],
+            ]
+        );
+
+        // square 1024 of the spiral memory is 31 steps away from the origin
+        let [i, j] = spiral().nth(1023).unwrap();
+        assert_eq!(i.abs() + j.abs(), 31);
+
+        let unique: HashSet<[i64; 2]> = spiral().take(10_000).collect();
+        assert_eq!(unique.len(), 10_000);
+    }
+
+    #[test]
+    fn test_2d_iter_present_in_extent() {
+        let mut map: Map<[i64; 2], char> = Map::new();
+        map.fixed_extent = Some(([-1_000_000_000, 0], [1_000_000_000, 1_000_000_000]));
+
+        map.set([5, 3], 'c');
+        map.set([-7, 900_000_000], 'a');
+        map.set([5, -1], 'x');
+        map.set([5, 2], 'b');
+
+        let tiles: Vec<([i64; 2], char)> =
+            map.iter_present_in_extent().map(|(p, t)| (p, *t)).collect();
+
+        assert_eq!(
+            tiles,
+            vec![([-7, 900_000_000], 'a'), ([5, 2], 'b'), ([5, 3], 'c')]
+        );
+
+        assert_eq!(
+            Map::<[i64; 2], char>::new()
+                .iter_present_in_extent()
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_2d_region_corners() {
+        let map: Map<[usize; 2], char> = "AAB\nAAB\nBBB".parse().unwrap();
+
+        let block: HashSet<[usize; 2]> = HashSet::from([[0, 0], [0, 1], [1, 0], [1, 1]]);
+        assert_eq!(map.region_corners(&block), 4);
+
+        let plus: HashSet<[usize; 2]> = HashSet::from([[0, 1], [1, 0], [1, 1], [1, 2], [2, 1]]);
+        assert_eq!(map.region_corners(&plus), 12);
+
+        // the L-shaped B region of the map has 6 sides
+        let components = map.connected_components(|&c| c == 'B');
+        assert_eq!(map.region_corners(&components[0]), 6);
+    }
+
+    #[test]
+    fn test_2d_present_neighbors4() {
+        let map: Map<[usize; 2], char> = "ab \n c \nd  ".parse().unwrap();
+
+        let neighbors: HashSet<([usize; 2], char)> = map
+            .present_neighbors4([0, 1])
+            .map(|(p, t)| (p, *t))
+            .collect();
+        assert_eq!(neighbors, HashSet::from([([0, 0], 'a'), ([1, 1], 'c')]));
+
+        assert_eq!(map.present_neighbors4([2, 0]).count(), 0);
+    }
+
+    #[test]
+    fn test_intersect_keys() {
+        let values: Map<[i32; 2], Digit> = "123\n456".parse().unwrap();
+
+        let mut region: Map<[i32; 2], bool> = Map::new();
+        region.set([0, 1], true);
+        region.set([1, 2], true);
+        region.set([5, 5], true);
+
+        let masked = values.intersect_keys(&region);
+        assert_eq!(masked.data.len(), 2);
+        assert_eq!(masked.get(&[0, 1]), Some(&Digit(2)));
+        assert_eq!(masked.get(&[1, 2]), Some(&Digit(6)));
+        assert_eq!(masked.get(&[5, 5]), None);
+    }
+
+    #[test]
+    fn test_2d_trace_loop() {
+        use Direction::*;
+
+        let pipes = |c: &char| match c {
+            '|' => vec![North, South],
+            '-' => vec![East, West],
+            'L' => vec![North, East],
+            'J' => vec![North, West],
+            '7' => vec![South, West],
+            'F' => vec![South, East],
+            _ => vec![],
+        };
+
+        let map: Map<[usize; 2], char> = ".....\n.S-7.\n.|.|.\n.L-J.\n.....".parse().unwrap();
+        let path = map.trace_loop([1, 1], pipes).unwrap();
+
+        assert_eq!(path.len(), 8);
+        assert_eq!(path[0], [1, 1]);
+        assert_eq!(path[4], [3, 3]);
+        assert_eq!(
+            path.iter().copied().collect::<HashSet<_>>().len(),
+            path.len()
+        );
+        for w in path.windows(2) {
+            let d = [
+                w[1][0] as i32 - w[0][0] as i32,
+                w[1][1] as i32 - w[0][1] as i32,
+            ];
+            assert!(Direction::from_delta(d).is_some());
+        }
+
+        // a broken loop is not found
+        let broken: Map<[usize; 2], char> = ".....\n.S-7.\n.|...\n.L-J.\n.....".parse().unwrap();
+        assert_eq!(broken.trace_loop([1, 1], pipes), None);
+
+        assert_eq!(map.trace_loop([0, 0], pipes), None);
+    }
+
+    #[test]
+    fn test_2d_reachable_in() {
+        let map: Map<[i32; 2], char> =
+            "...........\n.....###.#.\n.###.##..#.\n..#.#...#..\n....#.#....\n.##..S####.\n.##..#...#.\n.......##..\n.##.#.####.\n.##..##.##.\n..........."
+                .parse()
+                .unwrap();
+        let start = map.find_one(&'S').unwrap();
+        let passable = |c: &char| *c != '#';
+
+        // brute force by tracking all positions step by step
+        let mut positions = HashSet::from([start]);
+        for steps in 1..=6 {
+            positions = positions
+                .iter()
+                .flat_map(|p| neighbors_2d(*p))
+                .filter(|p| map.get(p).is_some_and(passable))
+                .collect();
+
+            assert_eq!(map.reachable_in(start, steps, passable), positions.len());
+        }
+
+        assert_eq!(positions.len(), 16);
+    }
+
+    #[test]
+    fn test_2d_read_y_up() {
+        let map: Map<[usize; 2], char> = Map::read_y_up(&mut "a..\n.b.\n..c\n".as_bytes()).unwrap();
+
+        assert_eq!(map.get(&[2, 0]), Some(&'a'));
+        assert_eq!(map.get(&[1, 1]), Some(&'b'));
+        assert_eq!(map.get(&[0, 2]), Some(&'c'));
+
+        // rows are counted from the last line, even if it has no tiles
+        let map: Map<[i32; 2], char> = Map::read_y_up(&mut "#\n \n ".as_bytes()).unwrap();
+        assert_eq!(map.get(&[2, 0]), Some(&'#'));
+        assert_eq!(map.data.len(), 1);
+    }
+
+    #[test]
+    fn test_assert_map_eq() {
+        let a: Map<[i32; 2], char> = "ab\ncd".parse().unwrap();
+        let b: Map<[i32; 2], char> = "ab\nc".parse().unwrap();
+
+        assert_map_eq(&a, &a.clone());
+
+        let err = std::panic::catch_unwind(|| assert_map_eq(&a, &b)).unwrap_err();
+        let message = err.downcast_ref::<String>().unwrap();
+        assert_eq!(
+            message,
+            "Map mismatch:\na (extent ([0, 0], [1, 1])):\nab\ncd\n\nb (extent ([0, 0], [1, 1])):\nab\nc \n"
+        );
     }
 
-    fn elementwise_max(a: Self, b: Self) -> Self {
-        [
-            std::cmp::max(a[0], b[0]),
-            std::cmp::max(a[1], b[1]),
-            std::cmp::max(a[2], b[2]),
-            std::cmp::max(a[3], b[3]),
-        ]
-    }
+    #[test]
+    fn test_2d_from_lines() {
+        let map: Map<[i32; 2], char> = Map::from_lines(&["ab", "cd"]).unwrap();
+        assert_map_eq(&map, &"ab\ncd".parse().unwrap());
 
-    fn extent_iterator(min: Self, max: Self) -> Self::ExtentIter {
-        Extent4DIterator {
-            min,
-            max,
-            current: Some(min.clone()),
-        }
+        let map: Map<[i32; 2], char> = Map::from_lines(&[]).unwrap();
+        assert!(map.data.is_empty());
     }
-}
 
-pub struct Extent4DIterator<I>
-where
-    I: IntCoord,
-{
-    min: [I; 4],
-    max: [I; 4],
-    current: Option<[I; 4]>,
-}
+    #[test]
+    fn test_2d_find_pattern() {
+        let map: Map<[usize; 2], char> = "#..#\n#...\n...#\n...#".parse().unwrap();
+        let pattern: Map<[usize; 2], char> = "#\n#".parse().unwrap();
 
-impl<I> Iterator for Extent4DIterator<I>
-where
-    I: IntCoord,
-{
-    type Item = [I; 4];
+        assert_eq!(
+            map.find_pattern(&pattern, |a, b| a == b),
+            vec![[0, 0], [2, 3]]
+        );
 
-    fn next(&mut self) -> Option<Self::Item> {
-        if let Some(c) = self.current {
-            let mut next = c.clone();
+        // patterns with gaps only constrain their present tiles
+        let monster: Map<[usize; 2], char> = "#  #".parse().unwrap();
+        assert_eq!(map.find_pattern(&monster, |a, b| a == b), vec![[0, 0]]);
 
-            next[0] = next[0] + I::one();
-            if next[0] > self.max[0] {
-                next[0] = self.min[0];
-                next[1] = next[1] + I::one();
-            }
+        let any: Map<[usize; 2], char> = "??".parse().unwrap();
+        assert_eq!(map.find_pattern(&any, |_, _| true).len(), 12);
+    }
 
-            if next[1] > self.max[1] {
-                next[1] = self.min[1];
-                next[2] = next[2] + I::one();
-            }
+    #[test]
+    fn test_2d_ray() {
+        let map: Map<[usize; 2], char> = "a b c\n.....".parse().unwrap();
 
-            if next[2] > self.max[2] {
-                next[2] = self.min[2];
-                next[3] = next[3] + I::one();
-            }
+        let east: Vec<([usize; 2], Option<char>)> = map
+            .ray([0, 1], Direction::East)
+            .map(|(p, t)| (p, t.copied()))
+            .collect();
+        assert_eq!(
+            east,
+            vec![([0, 2], Some('b')), ([0, 3], None), ([0, 4], Some('c'))]
+        );
 
-            if next[3] > self.max[3] {
-                self.current = None
-            } else {
-                self.current = Some(next)
+        assert_eq!(map.ray([0, 4], Direction::East).count(), 0);
+        assert_eq!(map.ray([0, 0], Direction::North).count(), 0);
+        assert_eq!(
+            map.ray([0, 2], Direction::South).last(),
+            Some(([1, 2], Some(&'.')))
+        );
+    }
+
+    #[test]
+    fn test_3d_rotate_axis() {
+        let mut map: Map<[i32; 3], char> = Map::new();
+        map.set([1, 2, 3], 'a');
+        map.set([0, 0, 0], 'b');
+        map.set([-4, 0, 1], 'c');
+
+        for axis in 0..3 {
+            let mut rotated = map.clone();
+            for _ in 0..4 {
+                rotated = rotated.rotate_axis(axis, 1);
             }
+            assert_eq!(rotated, map);
+            assert_eq!(map.rotate_axis(axis, -1), map.rotate_axis(axis, 3));
+            assert_ne!(map.rotate_axis(axis, 1), map);
+        }
 
-            Some(c)
-        } else {
-            None
+        assert_eq!(map.rotate_axis(2, 1).find_one(&'a'), Some([-2, 1, 3]));
+        assert_eq!(map.rotate_axis(0, 1).find_one(&'a'), Some([1, -3, 2]));
+
+        let orientations = map.orientations_3d();
+        assert_eq!(orientations.len(), 24);
+        assert_eq!(orientations[0], map);
+
+        // orientations are indexed like `math::rotate_3d`
+        for (index, orientation) in orientations.iter().enumerate() {
+            for (pos, tile) in map.data.iter() {
+                let rotated = crate::math::rotate_3d(*pos, index);
+                assert_eq!(
+                    orientation.get(&rotated),
+                    Some(tile),
+                    "orientation {}",
+                    index
+                );
+            }
         }
-    }
-}
 
-impl<T, I> Map<[I; 4], T>
-where
-    T: MapTile,
-    I: IntCoord,
-{
-    /// Convert a 3D map to a single-layered 4D map
-    pub fn from_3d(map: &Map<[I; 3], T>) -> Self {
-        let data: HashMap<[I; 4], T> = map
-            .data
+        // and agree with rotating around single axes
+        assert_eq!(orientations[5], map.rotate_axis(1, 1).rotate_axis(0, 1));
+        assert_eq!(orientations[19], map.rotate_axis(2, 1).rotate_axis(0, 3));
+
+        let distinct: HashSet<Vec<([i32; 3], char)>> = orientations
             .iter()
-            .map(|(key, tile)| {
-                let key = [I::zero(), key[0], key[1], key[2]];
-                (key, tile.clone())
+            .map(|m| {
+                let mut tiles: Vec<([i32; 3], char)> =
+                    m.data.iter().map(|(p, t)| (*p, *t)).collect();
+                tiles.sort();
+                tiles
             })
             .collect();
-
-        Map {
-            data,
-            fixed_extent: None,
-        }
+        assert_eq!(distinct.len(), 24);
     }
 
-    /// Slice a 4D map into a 3D map along one dimension
-    pub fn slice(&self, i: I, axis: usize) -> Map<[I; 3], T> {
-        let (ax0, ax1, ax2) = match axis {
-            0 => (1, 2, 3),
-            1 => (0, 2, 3),
-            2 => (0, 1, 3),
-            3 => (0, 1, 2),
-            _ => panic!("Invalid axis: {}", axis),
-        };
+    #[test]
+    fn test_2d_neighbors_where() {
+        let map: Map<[i32; 2], Digit> = "919\n1 9\n991".parse().unwrap();
+        let high = |d: &Digit| d.0 > 5;
 
-        let data: HashMap<[I; 3], T> = self
-            .data
-            .iter()
-            .filter_map(|(k, t)| {
-                if k[axis] == i {
-                    Some(([k[ax0], k[ax1], k[ax2]], t.clone()))
-                } else {
-                    None
-                }
-            })
+        let orthogonal: HashSet<[i32; 2]> = map
+            .neighbors_where([1, 1], false, high)
+            .into_iter()
             .collect();
+        assert_eq!(orthogonal, HashSet::from([[1, 2], [2, 1]]));
 
-        let fixed_extent = self.fixed_extent.map(|(min, max)| {
-            (
-                [min[ax0], min[ax1], min[ax2]],
-                [max[ax0], max[ax1], max[ax2]],
-            )
-        });
+        let all: HashSet<[i32; 2]> = map
+            .neighbors_where([1, 1], true, high)
+            .into_iter()
+            .collect();
+        assert_eq!(all, HashSet::from([[0, 0], [0, 2], [1, 2], [2, 0], [2, 1]]));
 
-        Map { data, fixed_extent }
+        assert!(map.neighbors_where([5, 5], true, high).is_empty());
     }
 
-    pub fn to_vecs(&self) -> Vec<Vec<Vec<Vec<Option<T>>>>> {
-        let (min, max) = self.get_extent();
+    #[test]
+    fn test_step_life_sparse() {
+        let blinker: Map<[i32; 2], char> = "...\n###\n...".parse().unwrap();
 
-        num::iter::range_inclusive(min[0], max[0])
-            .map(|i| {
-                num::iter::range_inclusive(min[1], max[1])
-                    .map(|j| {
-                        num::iter::range_inclusive(min[2], max[2])
-                            .map(|k| {
-                                num::iter::range_inclusive(min[3], max[3])
-                                    .map(|l| self.data.get(&[i, j, k, l]).cloned())
-                                    .collect()
-                            })
-                            .collect()
-                    })
-                    .collect()
-            })
-            .collect()
+        let mut map: Map<[i32; 2], ()> = Map::new();
+        for p in blinker.find_all(&'#') {
+            map.set(p, ());
+        }
+
+        let next = map.step_life_sparse(&[3], &[2, 3], true);
+        let mut cells: Vec<[i32; 2]> = next.data.keys().copied().collect();
+        cells.sort();
+        assert_eq!(cells, vec![[0, 1], [1, 1], [2, 1]]);
+        assert_eq!(next.step_life_sparse(&[3], &[2, 3], true), map);
+
+        // 3D, neighbors only along the axes
+        let mut cube: Map<[i64; 3], ()> = Map::new();
+        cube.set([0, 0, 0], ());
+        let next = cube.step_life_sparse(&[1], &[], false);
+        assert_eq!(next.data.len(), 6);
+        assert!(next.get(&[0, 0, -1]).is_some());
+        assert!(next.get(&[0, 1, 1]).is_none());
     }
-}
 
-impl<T, I> std::fmt::Display for Map<[I; 4], T>
-where
-    T: MapTile,
-    I: IntCoord,
-{
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::result::Result<(), std::fmt::Error> {
-        if self.data.is_empty() {
-            return Ok(());
+    #[test]
+    fn test_step_life_sparse_hex() {
+        // axial hex coordinates as used for the hex floor tiles
+        let hex_neighbors = |[q, r]: &[i32; 2]| {
+            [[1, 0], [-1, 0], [0, 1], [0, -1], [1, -1], [-1, 1]]
+                .iter()
+                .map(|[dq, dr]| [q + dq, r + dr])
+                .collect()
+        };
+
+        let mut map: Map<[i32; 2], ()> = Map::new();
+        for p in [[0, 0], [1, 0], [2, 0]].iter() {
+            map.set(*p, ());
         }
 
-        let (min, max) = self.get_extent();
+        // black tiles stay black with 1 or 2 black neighbors, white ones flip with exactly 2
+        let next = map.step_life_sparse_with(&[2], &[1, 2], hex_neighbors);
+        let cells: HashSet<[i32; 2]> = next.data.keys().copied().collect();
+        assert_eq!(
+            cells,
+            HashSet::from([[0, 0], [1, 0], [2, 0], [0, 1], [1, -1], [1, 1], [2, -1]])
+        );
+    }
 
-        for i in num::iter::range_inclusive(min[0], max[0]) {
-            for j in num::iter::range_inclusive(min[1], max[1]) {
-                write!(
-                    f,
-                    "Layer {}, {} =========\n{}\n",
-                    i,
-                    j,
-                    self.slice(i, 0).slice(j, 0)
-                )?;
-            }
-        }
+    #[test]
+    fn test_2d_local_extrema() {
+        let map: Map<[usize; 2], u8> = Map::from_char_grid(
+            "2199943210\n3987894921\n9856789892\n8767896789\n9899965678",
+            |c| c.to_digit(10).map(|d| d as u8),
+        );
 
-        Ok(())
+        let minima = map.local_minima();
+        assert_eq!(minima, vec![[0, 1], [0, 9], [2, 2], [4, 6]]);
+
+        let risk: usize = minima
+            .iter()
+            .map(|p| map.get(p).unwrap() + 1)
+            .map(usize::from)
+            .sum();
+        assert_eq!(risk, 15);
+
+        // 9s next to other 9s are not strict maxima
+        assert!(!map.local_maxima().contains(&[0, 2]));
+        let maxima = map.local_maxima();
+        assert_eq!(
+            maxima,
+            vec![
+                [1, 1],
+                [1, 5],
+                [1, 7],
+                [2, 0],
+                [2, 6],
+                [2, 8],
+                [3, 5],
+                [3, 9],
+                [4, 0]
+            ]
+        );
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[test]
+    fn test_2d_tour() {
+        let map: Map<[i32; 2], char> =
+            "###########\n#0.1.....2#\n#.#######.#\n#4.......3#\n###########"
+                .parse()
+                .unwrap();
+        let passable = |c: &char| *c != '#';
+
+        let start = map.find_one(&'0').unwrap();
+        let waypoints: Vec<[i32; 2]> = ['1', '2', '3', '4']
+            .iter()
+            .map(|c| map.find_one(c).unwrap())
+            .collect();
 
-    impl ParseMapTile for char {
-        fn from_char(c: char) -> Option<Self> {
-            if c == ' ' {
-                None
-            } else {
-                Some(c)
-            }
-        }
+        assert_eq!(map.tour(start, &waypoints, passable, false), Some(14));
+        assert_eq!(map.tour(start, &waypoints, passable, true), Some(20));
+
+        // brute force over all orders of three waypoints
+        let three = &waypoints[..3];
+        let dist = |a: [i32; 2], b: [i32; 2]| map.bfs(a, passable)[&b];
+        let orders = [
+            [0, 1, 2],
+            [0, 2, 1],
+            [1, 0, 2],
+            [1, 2, 0],
+            [2, 0, 1],
+            [2, 1, 0],
+        ];
+        let brute = orders
+            .iter()
+            .map(|o| {
+                dist(start, three[o[0]])
+                    + dist(three[o[0]], three[o[1]])
+                    + dist(three[o[1]], three[o[2]])
+            })
+            .min();
+        assert_eq!(map.tour(start, three, passable, false), brute);
+
+        assert_eq!(map.tour(start, &[], passable, true), Some(0));
+        assert_eq!(map.tour(start, &[[0, 0]], passable, false), None);
     }
 
-    fn assert_map_eq<I, T>(a: &Map<[I; 2], T>, b: &Map<[I; 2], T>)
-    where
-        I: IntCoord,
-        T: MapTile + PartialEq,
-    {
-        if a != b {
-            panic!(
-                "Map mismatch:\na (extent {:?}):\n{}\nb (extent {:?}):\n{}",
-                a.get_extent(),
-                a,
-                b.get_extent(),
-                b
-            )
-        }
+    #[test]
+    fn test_2d_parse_strict() {
+        let err = Map::<[i32; 2], Digit>::parse_strict("123\n45?\n7 9").unwrap_err();
+        assert_eq!(err.to_string(), "Bad tile '?' at line 1, column 2");
     }
 
     #[test]
-    fn test_2d_parsing() {
-        let map_string = "ab \nd e";
-        let map = Map::<[usize; 2], char>::read(&mut map_string.as_bytes()).unwrap();
+    fn test_2d_to_vecs_or() {
+        let map: Map<[i32; 2], char> = "ab\nc ".parse().unwrap();
+        assert_eq!(map.to_vecs_or('.'), vec![vec!['a', 'b'], vec!['c', '.']]);
 
-        assert_eq!(map.get_extent(), ([0, 0], [1, 2]));
+        assert!(Map::<[i32; 2], char>::new().to_vecs_or('.').is_empty());
+    }
+
+    #[test]
+    fn test_to_strings() {
+        let mut map: Map<[i32; 2], char> = Map::new();
+        map.set([0, 0], 'a');
+        map.set([0, 2], 'c');
+        map.set([1, 1], '5');
+
+        assert_eq!(map.to_strings('.'), vec!["a.c", ".5."]);
+        assert!(Map::<[i32; 2], char>::new().to_strings('.').is_empty());
+    }
+
+    #[test]
+    fn test_border_tiles() {
+        let map: Map<[i32; 2], char> = "#####\n#.a.#\n#...#\n##.##\n".parse().unwrap();
 
+        let border = map.border_tiles();
+        assert_eq!(border.len(), 2 * (4 + 5) - 4);
+        assert!(border.iter().all(|(_, c)| **c != 'a'));
+        assert_eq!(border[0], ([0, 0], &'#'));
         assert_eq!(
-            map.to_vecs(),
+            border
+                .iter()
+                .filter(|(_, c)| **c == '.')
+                .collect::<Vec<_>>(),
+            vec![&([3, 2], &'.')]
+        );
+
+        // holes on the ring are skipped
+        let sparse: Map<[i32; 2], char> =
+            Map::from_char_grid("# #\n.x \n  #\n", |c| if c == ' ' { None } else { Some(c) });
+        assert_eq!(
+            sparse.border_tiles(),
             vec![
-                vec![Some('a'), Some('b'), None],
-                vec![Some('d'), None, Some('e')],
+                ([0, 0], &'#'),
+                ([0, 2], &'#'),
+                ([2, 2], &'#'),
+                ([1, 0], &'.')
             ]
-        )
+        );
+
+        assert!(Map::<[i32; 2], char>::new().border_tiles().is_empty());
     }
 
     #[test]
-    fn test_2d_editing() {
-        let mut map: Map<[usize; 2], char> = Map::new();
+    fn test_elementwise_add_sub() {
+        let mut a: Map<[i32; 2], i64> = Map::new();
+        a.set([0, 0], 5);
+        a.set([0, 1], 3);
+        a.set([1, 1], -2);
+
+        let mut b: Map<[i32; 2], i64> = Map::new();
+        b.set([0, 0], 1);
+        b.set([1, 1], 4);
+        b.set([2, 0], 7);
+
+        let mut diff: Map<[i32; 2], i64> = Map::new();
+        diff.set([0, 0], 4);
+        diff.set([0, 1], 3);
+        diff.set([1, 1], -6);
+        diff.set([2, 0], -7);
+        assert_eq!(a.sub(&b), diff);
+
+        let mut sum: Map<[i32; 2], i64> = Map::new();
+        sum.set([0, 0], 6);
+        sum.set([0, 1], 3);
+        sum.set([1, 1], 2);
+        sum.set([2, 0], 7);
+        assert_eq!(a.add(&b), sum);
+
+        // coordinates only present in `other` are kept, even when they cancel out
+        let round_trip = a.sub(&b).add(&b);
+        assert_eq!(round_trip.get(&[2, 0]), Some(&0));
+        assert_eq!(round_trip.get(&[1, 1]), Some(&-2));
+    }
 
-        assert_eq!(map.get(&[1, 2]), None);
-        map.set([1, 2], 'a');
-        assert_eq!(map.get(&[1, 2]), Some(&'a'));
+    #[test]
+    fn test_read_stacked() {
+        let input = "#.#\n...\n---\n\n.#\n#.\n";
+        let maps: Vec<Map<[i32; 2], char>> =
+            Map::read_stacked(&mut input.as_bytes(), "---").unwrap();
+
+        assert_eq!(maps.len(), 2);
+        assert_eq!(maps[0], "#.#\n...\n".parse().unwrap());
+
+        // the blank first row of the second grid is kept
+        assert_eq!(maps[1].get(&[1, 1]), Some(&'#'));
+        assert_eq!(maps[1].get(&[2, 0]), Some(&'#'));
+        assert_eq!(maps[1].data.len(), 4);
+    }
 
-        map.set([4, 1], 'c');
-        assert_eq!(map.get_extent(), ([1, 1], [4, 2]));
+    #[test]
+    fn test_rle() {
+        let map: Map<[i32; 2], bool> =
+            Map::from_char_grid("##..\n.###\n...#\n", |c| Some(c == '#'));
 
-        map.set([8, 8], 'd');
-        assert_eq!(map.get_extent(), ([1, 1], [8, 8]));
+        let runs = map.rle();
+        assert_eq!(
+            runs,
+            vec![(true, 2), (false, 3), (true, 3), (false, 3), (true, 1)]
+        );
+        assert_eq!(Map::from_rle(&runs, map.get_extent()), map);
 
-        map.remove(&[8, 8]);
-        assert_eq!(map.get_extent(), ([1, 1], [4, 2]));
+        // missing tiles are encoded as false
+        let mut sparse: Map<[i32; 2], bool> = Map::new();
+        sparse.set([1, 1], true);
+        sparse.set([2, 3], true);
+        assert_eq!(sparse.rle(), vec![(true, 1), (false, 4), (true, 1)]);
 
-        assert_eq!(
-            map.to_vecs(),
-            vec![
-                vec![None, Some('a')],
-                vec![None, None],
-                vec![None, None],
-                vec![Some('c'), None],
-            ]
-        )
+        assert!(Map::<[i32; 2], bool>::new().rle().is_empty());
     }
 
     #[test]
-    fn test_2d_display() {
-        let map_string = "ab \nd e";
-        let map = Map::<[usize; 2], char>::read(&mut map_string.as_bytes()).unwrap();
+    fn test_region_with_border_counts() {
+        let map: Map<[i32; 2], char> = "AAAA\nABBA\nABBA\nAAAC\n".parse().unwrap();
 
-        assert_eq!(format!("{}", map), format!("{}\n", map_string));
+        // the B plot is enclosed entirely by A
+        let (region, counts) = map.region_with_border_counts([1, 1], |t| *t == 'B');
+        assert_eq!(region, HashSet::from([[1, 1], [1, 2], [2, 1], [2, 2]]));
+        assert_eq!(counts, HashMap::from([('A', 8)]));
 
-        let map2: Map<[usize; 2], char> = Map::new();
-        assert_eq!(format!("{}", map2), "");
+        let (region, counts) = map.region_with_border_counts([3, 3], |t| *t == 'C');
+        assert_eq!(region, HashSet::from([[3, 3]]));
+        assert_eq!(counts, HashMap::from([('A', 2)]));
+
+        let (region, counts) = map.region_with_border_counts([0, 0], |t| *t == 'B');
+        assert!(region.is_empty() && counts.is_empty());
     }
 
     #[test]
-    fn test_2d_rotating_flipping() {
-        //              R    L
-        //  j 01234    012  012
-        // i
-        // 0  abcde    kfa  ejo
-        // 1  fghIj    Lgb  dIn
-        // 2  kLmno    mhc  chm
-        // 3           nId  bgL
-        // 4           oje  afk
-        //
-        //
-        // FLIP i      FLIP j
-        //
-        //   kLmno     edcba
-        //   fghIj     jIhgf
-        //   abcde     onmLk
+    fn test_cycle_row_col() {
+        let mut map: Map<[i32; 2], char> = Map::from_dense("ab.d\nefgh\n", '.');
 
-        let map = "abcde\nfghIj\nkLmno"
-            .parse::<Map<[usize; 2], char>>()
-            .unwrap();
+        map.cycle_row(0, 1);
+        assert_eq!(map.to_strings('.'), vec!["dab.", "efgh"]);
 
-        assert_map_eq(
-            &map.rotate_right(),
-            &"kfa\nLgb\nmhc\nnId\noje".parse().unwrap(),
-        );
+        map.cycle_row(1, -1);
+        assert_eq!(map.to_strings('.'), vec!["dab.", "fghe"]);
 
-        assert_map_eq(
-            &map.rotate_left(),
-            &"ejo\ndIn\nchm\nbgL\nafk".parse().unwrap(),
-        );
+        map.cycle_col(2, 3);
+        assert_eq!(map.to_strings('.'), vec!["dah.", "fgbe"]);
 
-        assert_map_eq(&map.flip(0), &"kLmno\nfghIj\nabcde".parse().unwrap());
-        assert_map_eq(&map.flip(1), &"edcba\njIhgf\nonmLk".parse().unwrap());
+        // unsigned coordinates wrap around by shifting the remaining distance
+        let mut map: Map<[usize; 2], char> = Map::from_dense("abc\n", '.');
+        map.cycle_row(0, 5);
+        assert_eq!(map.to_strings('.'), vec!["bca"]);
     }
 }
@@ -16,3 +16,723 @@ pub fn lcm(a: usize, b: usize) -> usize {
 pub fn lcm_multiple(n: &[usize]) -> usize {
     n.iter().cloned().reduce(lcm).unwrap_or(0)
 }
+
+/// Calculate the n-th triangular number, i.e. the sum of 1..=n
+pub fn triangular(n: u64) -> u64 {
+    n * (n + 1) / 2
+}
+
+/// Calculate the sum of an arithmetic series of `count` terms from `first` to `last`
+pub fn arithmetic_sum(first: i64, last: i64, count: i64) -> i64 {
+    (first + last) * count / 2
+}
+
+/// Get the median of a slice, using the lower median for an even number of elements
+pub fn median<T: Ord + Clone>(xs: &[T]) -> Option<T> {
+    if xs.is_empty() {
+        return None;
+    }
+
+    let mut sorted = xs.to_vec();
+    sorted.sort();
+
+    Some(sorted[(sorted.len() - 1) / 2].clone())
+}
+
+/// Get the most frequent element of a slice, preferring the smallest element on ties
+pub fn mode<T: Ord + Clone>(xs: &[T]) -> Option<T> {
+    let mut sorted = xs.to_vec();
+    sorted.sort();
+
+    let mut best: Option<(&T, usize)> = None;
+    for run in sorted.chunk_by(|a, b| a == b) {
+        if best.is_none_or(|(_, n)| run.len() > n) {
+            best = Some((&run[0], run.len()));
+        }
+    }
+
+    best.map(|(x, _)| x.clone())
+}
+
+/// Solve the 0/1 knapsack problem for `(weight, value)` items, returning the best total value
+/// achievable without exceeding `capacity`
+pub fn knapsack_01(items: &[(usize, usize)], capacity: usize) -> usize {
+    // best[c] is the best value achievable with a total weight of at most c
+    let mut best = vec![0; capacity + 1];
+
+    for &(weight, value) in items {
+        for c in (weight..=capacity).rev() {
+            best[c] = best[c].max(best[c - weight] + value);
+        }
+    }
+
+    best[capacity]
+}
+
+/// Get the angle of the direction from `from` to `to` in radians, measured clockwise from
+/// "up" in `[i, j]` row/column coordinates and normalized to `0..2π`
+pub fn angle_from(from: [i64; 2], to: [i64; 2]) -> f64 {
+    let di = (to[0] - from[0]) as f64;
+    let dj = (to[1] - from[1]) as f64;
+
+    let angle = dj.atan2(-di);
+    if angle < 0.0 {
+        angle + 2.0 * std::f64::consts::PI
+    } else {
+        angle
+    }
+}
+
+/// Divide a step by the gcd of its components, so that all steps along the same ray from the
+/// origin share the same reduced slope
+pub fn reduced_slope(dx: i64, dy: i64) -> (i64, i64) {
+    let g = gcd(dx.unsigned_abs() as usize, dy.unsigned_abs() as usize) as i64;
+    if g == 0 {
+        (0, 0)
+    } else {
+        (dx / g, dy / g)
+    }
+}
+
+/// Parse a number written in base `radix`, using `digit` to get the value of each character.
+/// Digit values may be negative, e.g. for balanced bases.
+pub fn from_radix<D: Fn(char) -> i64>(s: &str, radix: u32, digit: D) -> i64 {
+    s.trim()
+        .chars()
+        .fold(0, |acc, c| acc * radix as i64 + digit(c))
+}
+
+/// Write a number in base `radix`, using `digit_char` to get the character for a digit value
+/// or `None` if the value is not a digit. Supports both regular (`0..radix`) and balanced
+/// digit ranges.
+///
+/// Panics if `n` cannot be represented with the available digits, e.g. for a negative `n`
+/// with regular digits only.
+pub fn to_radix<C: Fn(i64) -> Option<char>>(mut n: i64, radix: u32, digit_char: C) -> String {
+    let radix = radix as i64;
+
+    let mut out = Vec::new();
+    loop {
+        let r = n.rem_euclid(radix);
+        let (d, c) = [r, r - radix]
+            .iter()
+            .copied()
+            .find_map(|d| digit_char(d).map(|c| (d, c)))
+            .unwrap_or_else(|| panic!("No digit for {} in base {}", r, radix));
+
+        out.push(c);
+
+        let next = (n - d) / radix;
+        if next == 0 {
+            break;
+        }
+        assert_ne!(next, n, "Cannot represent {} in base {}", n, radix);
+        n = next;
+    }
+
+    out.iter().rev().collect()
+}
+
+/// Get the value of a SNAFU digit (balanced base 5 using `=-012`)
+pub fn snafu_digit(c: char) -> i64 {
+    match c {
+        '=' => -2,
+        '-' => -1,
+        '0' => 0,
+        '1' => 1,
+        '2' => 2,
+        _ => panic!("Bad SNAFU digit: '{}'", c),
+    }
+}
+
+/// Get the SNAFU character for a digit value, if it is one
+pub fn snafu_char(d: i64) -> Option<char> {
+    match d {
+        -2 => Some('='),
+        -1 => Some('-'),
+        0 => Some('0'),
+        1 => Some('1'),
+        2 => Some('2'),
+        _ => None,
+    }
+}
+
+/// Parse a number written in SNAFU (balanced base 5 using `=-012`)
+pub fn from_snafu(s: &str) -> i64 {
+    from_radix(s, 5, snafu_digit)
+}
+
+/// Write a number in SNAFU (balanced base 5 using `=-012`)
+pub fn to_snafu(n: i64) -> String {
+    to_radix(n, 5, snafu_char)
+}
+
+/// Split inclusive ranges at the boundaries of inclusive `cuts`, returning the pieces of every
+/// range in order, each tagged with the index of the cut it falls into, if any.
+///
+/// Cuts are expected not to overlap.
+pub fn split_ranges(ranges: &[(i64, i64)], cuts: &[(i64, i64)]) -> Vec<(i64, i64, Option<usize>)> {
+    let mut order: Vec<usize> = (0..cuts.len()).collect();
+    order.sort_by_key(|i| cuts[*i].0);
+
+    let mut out = Vec::new();
+    for &(start, end) in ranges.iter() {
+        let mut cursor = Some(start);
+
+        for &i in order.iter() {
+            let Some(pos) = cursor.filter(|pos| *pos <= end) else {
+                break;
+            };
+
+            let (cut_start, cut_end) = cuts[i];
+            if cut_end < pos {
+                continue;
+            }
+            if cut_start > end {
+                break;
+            }
+
+            if cut_start > pos {
+                out.push((pos, cut_start - 1, None));
+            }
+
+            let piece_end = cut_end.min(end);
+            out.push((cut_start.max(pos), piece_end, Some(i)));
+            cursor = piece_end.checked_add(1);
+        }
+
+        if let Some(pos) = cursor.filter(|pos| *pos <= end) {
+            out.push((pos, end, None));
+        }
+    }
+
+    out
+}
+
+/// Get the point where the segments `a1..a2` and `b1..b2` cross, including their end points.
+/// Parallel segments, even overlapping ones, and segments that do not reach each other give
+/// `None`.
+pub fn segments_intersect(
+    a1: [i64; 2],
+    a2: [i64; 2],
+    b1: [i64; 2],
+    b2: [i64; 2],
+) -> Option<[f64; 2]> {
+    // compute in i128 as the cross products of large coordinates overflow i64
+    let cross = |u: [i128; 2], v: [i128; 2]| u[0] * v[1] - u[1] * v[0];
+    let sub = |u: [i64; 2], v: [i64; 2]| [u[0] as i128 - v[0] as i128, u[1] as i128 - v[1] as i128];
+
+    let da = sub(a2, a1);
+    let db = sub(b2, b1);
+    let denom = cross(da, db);
+    if denom == 0 {
+        return None;
+    }
+
+    // a1 + t * da == b1 + u * db, with t = t_num / denom and u = u_num / denom
+    let d = sub(b1, a1);
+    let (mut t_num, mut u_num, mut denom) = (cross(d, db), cross(d, da), denom);
+    if denom < 0 {
+        t_num = -t_num;
+        u_num = -u_num;
+        denom = -denom;
+    }
+
+    if !(0..=denom).contains(&t_num) || !(0..=denom).contains(&u_num) {
+        return None;
+    }
+
+    let t = t_num as f64 / denom as f64;
+    Some([
+        a1[0] as f64 + t * da[0] as f64,
+        a1[1] as f64 + t * da[1] as f64,
+    ])
+}
+
+/// Get the dot product of two 3D vectors
+pub fn dot3<T>(a: [T; 3], b: [T; 3]) -> T
+where
+    T: Copy + std::ops::Add<Output = T> + std::ops::Mul<Output = T>,
+{
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+/// Get the cross product of two 3D vectors
+pub fn cross3<T>(a: [T; 3], b: [T; 3]) -> [T; 3]
+where
+    T: Copy + std::ops::Sub<Output = T> + std::ops::Mul<Output = T>,
+{
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0],
+    ]
+}
+
+/// Subtract two 3D vectors
+pub fn sub3<T>(a: [T; 3], b: [T; 3]) -> [T; 3]
+where
+    T: Copy + std::ops::Sub<Output = T>,
+{
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+/// Rotate a point a quarter turn around an axis, counter-clockwise when looking from the
+/// positive end of the axis towards the origin
+pub fn quarter_turn_3d<T>(p: [T; 3], axis: usize) -> [T; 3]
+where
+    T: Copy + std::ops::Neg<Output = T>,
+{
+    let (a, b) = match axis {
+        0 => (1, 2),
+        1 => (2, 0),
+        2 => (0, 1),
+        _ => panic!("Bad axis for 3D rotation: {}", axis),
+    };
+
+    let mut out = p;
+    out[a] = -p[b];
+    out[b] = p[a];
+    out
+}
+
+/// Rotate a point around the origin into one of the 24 orientations of a cube, with `index`
+/// in `0..24`. Orientation `index` points the first axis into one of six directions, given by
+/// `index / 4`, and then spins `index % 4` quarter turns around it.
+pub fn rotate_3d<T>(p: [T; 3], index: usize) -> [T; 3]
+where
+    T: Copy + std::ops::Neg<Output = T>,
+{
+    assert!(index < 24, "Bad orientation index: {}", index);
+
+    // (axis, quarter turns) to point the first axis into each of the six directions
+    const FACINGS: [(usize, usize); 6] = [(1, 0), (1, 1), (1, 2), (1, 3), (2, 1), (2, 3)];
+
+    let (axis, turns) = FACINGS[index / 4];
+    let mut p = p;
+    for _ in 0..turns {
+        p = quarter_turn_3d(p, axis);
+    }
+
+    for _ in 0..index % 4 {
+        p = quarter_turn_3d(p, 0);
+    }
+
+    p
+}
+
+/// Find an orientation and offset that maps at least `min_overlap` points of `b` onto points
+/// of `a`, returning the offset and the orientation index for `rotate_3d`, so that
+/// `rotate_3d(p, index) + offset` is in `a` for the overlapping points `p` of `b`
+pub fn align_clouds(
+    a: &[[i64; 3]],
+    b: &[[i64; 3]],
+    min_overlap: usize,
+) -> Option<([i64; 3], usize)> {
+    for index in 0..24 {
+        let mut offsets: std::collections::HashMap<[i64; 3], usize> =
+            std::collections::HashMap::new();
+
+        for pb in b.iter() {
+            let rb = rotate_3d(*pb, index);
+            for pa in a.iter() {
+                let offset = sub3(*pa, rb);
+                let count = offsets.entry(offset).or_insert(0);
+                *count += 1;
+
+                if *count >= min_overlap {
+                    return Some((offset, index));
+                }
+            }
+        }
+    }
+
+    None
+}
+
+/// Get the inverse of `a` modulo `m`, or `None` if `a` and `m` are not coprime
+pub fn mod_inverse(a: i128, m: i128) -> Option<i128> {
+    let (mut r0, mut r1) = (a.rem_euclid(m), m);
+    let (mut s0, mut s1) = (1i128, 0i128);
+
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (s0, s1) = (s1, s0 - q * s1);
+    }
+
+    if r0 == 1 {
+        Some(s0.rem_euclid(m))
+    } else {
+        None
+    }
+}
+
+/// An affine map `x -> a * x + b (mod modulus)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Affine {
+    pub a: i128,
+    pub b: i128,
+    pub modulus: i128,
+}
+
+impl Affine {
+    pub fn new(a: i128, b: i128, modulus: i128) -> Self {
+        Affine {
+            a: a.rem_euclid(modulus),
+            b: b.rem_euclid(modulus),
+            modulus,
+        }
+    }
+
+    /// The map that leaves every value unchanged
+    pub fn identity(modulus: i128) -> Self {
+        Affine::new(1, 0, modulus)
+    }
+
+    pub fn apply(&self, x: i128) -> i128 {
+        (self.a * x.rem_euclid(self.modulus) + self.b).rem_euclid(self.modulus)
+    }
+
+    /// Compose two maps into one that applies `self` first and `next` second
+    pub fn then(&self, next: &Affine) -> Affine {
+        assert_eq!(self.modulus, next.modulus, "Moduli of composed maps differ");
+
+        Affine::new(
+            next.a * self.a % self.modulus,
+            (next.a * self.b + next.b) % self.modulus,
+            self.modulus,
+        )
+    }
+
+    /// Compose the map with itself `n` times using repeated squaring
+    pub fn pow(&self, mut n: u64) -> Affine {
+        let mut out = Affine::identity(self.modulus);
+        let mut square = *self;
+
+        while n > 0 {
+            if n & 1 == 1 {
+                out = out.then(&square);
+            }
+            square = square.then(&square);
+            n >>= 1;
+        }
+
+        out
+    }
+
+    /// Get the map undoing this one, or `None` if `a` is not invertible modulo `modulus`
+    pub fn inverse(&self) -> Option<Affine> {
+        let a_inv = mod_inverse(self.a, self.modulus)?;
+        Some(Affine::new(
+            a_inv,
+            -(a_inv * self.b % self.modulus),
+            self.modulus,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_triangular() {
+        assert_eq!(triangular(0), 0);
+        assert_eq!(triangular(5), 15);
+        assert_eq!(triangular(11), (1..=11).sum());
+    }
+
+    #[test]
+    fn test_arithmetic_sum() {
+        // 3 + 7 + 11 + 15 + 19
+        assert_eq!(arithmetic_sum(3, 19, 5), 55);
+        // -4 + -2 + 0 + 2
+        assert_eq!(arithmetic_sum(-4, 2, 4), -4);
+    }
+
+    #[test]
+    fn test_median() {
+        assert_eq!(median(&[16, 1, 2, 0, 4, 2, 7, 1, 2, 14]), Some(2));
+        assert_eq!(median(&[5, 1, 3]), Some(3));
+        assert_eq!(median(&[4, 1, 3, 2]), Some(2));
+        assert_eq!(median::<i32>(&[]), None);
+    }
+
+    #[test]
+    fn test_mode() {
+        assert_eq!(mode(&[3, 1, 3, 2, 1, 3]), Some(3));
+        assert_eq!(mode(&[2, 1, 2, 1]), Some(1));
+        assert_eq!(mode::<i32>(&[]), None);
+    }
+
+    #[test]
+    fn test_knapsack_01() {
+        assert_eq!(knapsack_01(&[(10, 60), (20, 100), (30, 120)], 50), 220);
+        assert_eq!(knapsack_01(&[(1, 1), (3, 4), (4, 5), (5, 7)], 7), 9);
+        assert_eq!(knapsack_01(&[(5, 10)], 4), 0);
+        assert_eq!(knapsack_01(&[], 10), 0);
+    }
+
+    #[test]
+    fn test_reduced_slope() {
+        assert_eq!(reduced_slope(2, 4), (1, 2));
+        assert_eq!(reduced_slope(3, 6), (1, 2));
+        assert_eq!(reduced_slope(-3, 6), (-1, 2));
+        assert_eq!(reduced_slope(0, -5), (0, -1));
+        assert_eq!(reduced_slope(0, 0), (0, 0));
+        assert_ne!(reduced_slope(-2, -4), reduced_slope(2, 4));
+    }
+
+    #[test]
+    fn test_angle_from() {
+        use std::f64::consts::PI;
+
+        let station = [5, 5];
+        assert_eq!(angle_from(station, [0, 5]), 0.0);
+        assert!((angle_from(station, [5, 9]) - PI / 2.0).abs() < 1e-9);
+        assert!((angle_from(station, [8, 5]) - PI).abs() < 1e-9);
+        assert!((angle_from(station, [5, 1]) - 3.0 * PI / 2.0).abs() < 1e-9);
+
+        // a clockwise sweep starting upwards
+        let mut targets = vec![[6, 4], [4, 5], [5, 6], [4, 4], [6, 6], [4, 6]];
+        targets.sort_by(|a, b| {
+            angle_from(station, *a)
+                .partial_cmp(&angle_from(station, *b))
+                .unwrap()
+        });
+        assert_eq!(
+            targets,
+            vec![[4, 5], [4, 6], [5, 6], [6, 6], [6, 4], [4, 4]]
+        );
+
+        // points on the same ray share their angle
+        assert_eq!(angle_from(station, [3, 6]), angle_from(station, [1, 7]));
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        assert_eq!(mod_inverse(3, 10), Some(7));
+        assert_eq!(mod_inverse(-3, 10), Some(3));
+        assert_eq!(mod_inverse(4, 10), None);
+    }
+
+    #[test]
+    fn test_affine() {
+        // shuffle techniques as maps from card position before to position after
+        let n = 10;
+        let increment = |k| Affine::new(k, 0, n);
+        let new_stack = Affine::new(-1, -1, n);
+        let cut = |k: i128| Affine::new(1, -k, n);
+
+        let deal = |f: &Affine| {
+            let mut deck = vec![0; n as usize];
+            for card in 0..n {
+                deck[f.apply(card) as usize] = card;
+            }
+            deck
+        };
+
+        let shuffle = increment(7).then(&new_stack).then(&new_stack);
+        assert_eq!(deal(&shuffle), vec![0, 3, 6, 9, 2, 5, 8, 1, 4, 7]);
+
+        let shuffle = cut(6).then(&increment(7)).then(&new_stack);
+        assert_eq!(deal(&shuffle), vec![3, 0, 7, 4, 1, 8, 5, 2, 9, 6]);
+
+        let f = Affine::new(3, 5, 101);
+        let g = Affine::new(-8, 42, 101);
+        for x in 0..101 {
+            assert_eq!(f.then(&g).apply(x), g.apply(f.apply(x)));
+            assert_eq!(f.inverse().unwrap().apply(f.apply(x)), x);
+        }
+
+        let mut repeated = Affine::identity(101);
+        for _ in 0..1000 {
+            repeated = repeated.then(&f);
+        }
+        assert_eq!(f.pow(1000), repeated);
+        assert_eq!(f.pow(0), Affine::identity(101));
+
+        assert_eq!(Affine::new(2, 1, 10).inverse(), None);
+    }
+
+    #[test]
+    fn test_vector3() {
+        assert_eq!(cross3::<i128>([1, 0, 0], [0, 1, 0]), [0, 0, 1]);
+        assert_eq!(cross3::<i128>([0, 1, 0], [1, 0, 0]), [0, 0, -1]);
+        assert_eq!(cross3([2.0, 3.0, 4.0], [5.0, 6.0, 7.0]), [-3.0, 6.0, -3.0]);
+
+        assert_eq!(dot3::<i128>([1, 2, 3], [4, -5, 6]), 12);
+        assert_eq!(dot3([0.5, 0.0, 2.0], [4.0, 1.0, 0.25]), 2.5);
+
+        assert_eq!(sub3::<i128>([19, 13, 30], [18, 19, 22]), [1, -6, 8]);
+
+        // the cross product is orthogonal to both inputs
+        let (a, b) = ([19i128, 13, 30], [-2i128, 1, -2]);
+        let c = cross3(a, b);
+        assert_eq!(dot3(a, c), 0);
+        assert_eq!(dot3(b, c), 0);
+    }
+
+    #[test]
+    fn test_radix() {
+        let hex_digit = |c: char| c.to_digit(16).unwrap() as i64;
+        let hex_char = |d: i64| std::char::from_digit(std::convert::TryFrom::try_from(d).ok()?, 16);
+
+        assert_eq!(from_radix("ff", 16, hex_digit), 255);
+        assert_eq!(to_radix(255, 16, hex_char), "ff");
+        assert_eq!(to_radix(0, 16, hex_char), "0");
+        assert_eq!(to_radix(5, 2, hex_char), "101");
+    }
+
+    #[test]
+    #[should_panic(expected = "Cannot represent -1 in base 10")]
+    fn test_radix_negative() {
+        to_radix(-1, 10, |d| std::char::from_digit(d as u32, 10));
+    }
+
+    #[test]
+    fn test_snafu() {
+        let examples = [
+            (1, "1"),
+            (3, "1="),
+            (8, "2="),
+            (10, "20"),
+            (15, "1=0"),
+            (20, "1-0"),
+            (2022, "1=11-2"),
+            (12345, "1-0---0"),
+            (314159265, "1121-1110-1=0"),
+            (1747, "1=-0-2"),
+            (4890, "2=-1=0"),
+        ];
+
+        for (n, s) in examples.iter() {
+            assert_eq!(from_snafu(s), *n);
+            assert_eq!(to_snafu(*n), *s);
+        }
+
+        assert_eq!(to_snafu(0), "0");
+        assert_eq!(from_snafu(&to_snafu(-1747)), -1747);
+    }
+
+    #[test]
+    fn test_rotate_3d() {
+        let p = [1, 2, 3];
+        let rotated: std::collections::HashSet<[i64; 3]> =
+            (0..24).map(|i| rotate_3d(p, i)).collect();
+        assert_eq!(rotated.len(), 24);
+        assert_eq!(rotate_3d(p, 0), p);
+        assert_eq!(rotate_3d(p, 1), [1, -3, 2]);
+    }
+
+    #[test]
+    fn test_align_clouds() {
+        let shared: Vec<[i64; 3]> = (0..12)
+            .map(|i| [i * 7 % 13 - 6, i * i % 17 - 8, i * 5 % 11 + 2 * i])
+            .collect();
+
+        let (index, offset) = (17, [68, -1246, -43]);
+
+        let mut a: Vec<[i64; 3]> = shared
+            .iter()
+            .map(|p| {
+                let r = rotate_3d(*p, index);
+                [r[0] + offset[0], r[1] + offset[1], r[2] + offset[2]]
+            })
+            .collect();
+        a.push([500, 500, 500]);
+        a.push([-700, 3, 12]);
+
+        let mut b = shared.clone();
+        b.insert(3, [900, -900, 900]);
+
+        assert_eq!(align_clouds(&a, &b, 12), Some((offset, index)));
+        assert_eq!(align_clouds(&a, &b, 13), None);
+    }
+
+    #[test]
+    fn test_split_ranges() {
+        // 01234567890123456789
+        //
+        //        ssssssssss
+        //    xx aaa bb  cccccc
+        //        AABCCDDEEE
+        let cuts = [(3, 4), (6, 8), (10, 11), (14, 19)];
+
+        let pieces = split_ranges(&[(7, 16)], &cuts);
+        assert_eq!(
+            pieces,
+            vec![
+                (7, 8, Some(1)),
+                (9, 9, None),
+                (10, 11, Some(2)),
+                (12, 13, None),
+                (14, 16, Some(3)),
+            ]
+        );
+
+        // shifting the pieces within cuts reproduces the almanac remapping
+        let remapped: Vec<(i64, i64)> = pieces
+            .iter()
+            .map(|(s, e, cut)| match cut {
+                Some(_) => (s + 100, e + 100),
+                None => (*s, *e),
+            })
+            .collect();
+        assert_eq!(
+            remapped,
+            vec![(107, 108), (9, 9), (110, 111), (12, 13), (114, 116)]
+        );
+
+        assert_eq!(split_ranges(&[(30, 40)], &cuts), vec![(30, 40, None)]);
+        assert_eq!(split_ranges(&[(0, 2)], &cuts), vec![(0, 2, None)]);
+
+        // unsorted cuts keep their indices, and several ranges are split independently
+        assert_eq!(
+            split_ranges(
+                &[(0, 5), (i64::MAX - 1, i64::MAX)],
+                &[(i64::MAX, i64::MAX), (2, 3)]
+            ),
+            vec![
+                (0, 1, None),
+                (2, 3, Some(1)),
+                (4, 5, None),
+                (i64::MAX - 1, i64::MAX - 1, None),
+                (i64::MAX, i64::MAX, Some(0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_segments_intersect() {
+        assert_eq!(
+            segments_intersect([0, 0], [4, 4], [0, 4], [4, 0]),
+            Some([2.0, 2.0])
+        );
+        assert_eq!(
+            segments_intersect([0, 0], [3, 0], [1, -1], [2, 1]),
+            Some([1.5, 0.0])
+        );
+
+        // touching end points count as crossing
+        assert_eq!(
+            segments_intersect([0, 0], [2, 0], [2, 0], [2, 5]),
+            Some([2.0, 0.0])
+        );
+
+        // parallel and collinear segments
+        assert_eq!(segments_intersect([0, 0], [4, 0], [0, 1], [4, 1]), None);
+        assert_eq!(segments_intersect([0, 0], [4, 0], [2, 0], [6, 0]), None);
+
+        // the lines cross, but outside of one of the segments
+        assert_eq!(segments_intersect([0, 0], [1, 1], [0, 4], [4, 0]), None);
+
+        // coordinates as large as in the hailstone puzzle do not overflow
+        let big = 400_000_000_000_000;
+        assert_eq!(
+            segments_intersect([0, 0], [big, big], [0, big], [big, 0]),
+            Some([big as f64 / 2.0, big as f64 / 2.0])
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use crate::map::Map;
+
+/// Height of the letters in rows
+const HEIGHT: i32 = 6;
+
+/// Width of the letters in columns, not counting the blank column between letters
+const WIDTH: i32 = 4;
+
+/// The capital letters of the Advent of Code font that fit into 4 by 6 pixels, with the rows
+/// of each letter concatenated
+const FONT: [(char, &str); 17] = [
+    ('A', ".##.#..##..######..##..#"),
+    ('B', "###.#..####.#..##..####."),
+    ('C', ".##.#..##...#...#..#.##."),
+    ('E', "#####...###.#...#...####"),
+    ('F', "#####...###.#...#...#..."),
+    ('G', ".##.#..##...#.###..#.###"),
+    ('H', "#..##..######..##..##..#"),
+    ('I', ".###..#...#...#...#..###"),
+    ('J', "..##...#...#...##..#.##."),
+    ('K', "#..##.#.##..#.#.#.#.#..#"),
+    ('L', "#...#...#...#...#...####"),
+    ('O', ".##.#..##..##..##..#.##."),
+    ('P', "###.#..##..####.#...#..."),
+    ('R', "###.#..##..####.#.#.#..#"),
+    ('S', ".####...#....##....####."),
+    ('U', "#..##..##..##..##..#.##."),
+    ('Z', "####...#..#..#..#...####"),
+];
+
+/// Read the letters rendered as lit tiles of a map, using the Advent of Code font.
+///
+/// Letters are 4 pixels wide with a blank column between them, and the first letter starts at
+/// the top-left corner of the extent. Missing tiles are dark, and letters that are not
+/// recognized are returned as `?`.
+pub fn recognize(map: &Map<[i32; 2], bool>) -> String {
+    let Some((min, max)) = map.try_get_extent() else {
+        return String::new();
+    };
+
+    let lit = |i: i32, j: i32| map.get(&[i, j]).copied().unwrap_or(false);
+
+    let n_letters = (max[1] - min[1]) / (WIDTH + 1) + 1;
+    (0..n_letters)
+        .map(|k| {
+            let left = min[1] + k * (WIDTH + 1);
+            let pixels: String = (0..HEIGHT)
+                .flat_map(|di| (0..WIDTH).map(move |dj| (di, dj)))
+                .map(|(di, dj)| {
+                    if lit(min[0] + di, left + dj) {
+                        '#'
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+
+            FONT.iter()
+                .find(|(_, glyph)| *glyph == pixels)
+                .map_or('?', |(c, _)| *c)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recognize() {
+        let art = ".##..###...##..#..#.####.#....\n\
+                   #..#.#..#.#..#.#..#....#.#....\n\
+                   #..#.###..#....####...#..#....\n\
+                   ####.#..#.#....#..#..#...#....\n\
+                   #..#.#..#.#..#.#..#.#....#....\n\
+                   #..#.###...##..#..#.####.####.\n";
+
+        let dense: Map<[i32; 2], bool> = Map::from_char_grid(art, |c| Some(c == '#'));
+        assert_eq!(recognize(&dense), "ABCHZL");
+
+        // only storing lit pixels works as well
+        let sparse: Map<[i32; 2], bool> = Map::from_char_grid(art, |c| (c == '#').then_some(true));
+        assert_eq!(recognize(&sparse), "ABCHZL");
+
+        let unknown: Map<[i32; 2], bool> =
+            Map::from_char_grid("#..#\n####\n#..#\n#..#\n####\n#..#\n", |c| Some(c == '#'));
+        assert_eq!(recognize(&unknown), "?");
+
+        assert_eq!(recognize(&Map::new()), "");
+    }
+
+    #[test]
+    fn test_font() {
+        for (c, glyph) in FONT.iter() {
+            assert_eq!(glyph.len(), (WIDTH * HEIGHT) as usize, "glyph for {}", c);
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::{bail, Result};
+
+#[doc(hidden)]
+pub use inventory;
+
+/// A solution for one day of puzzles
+pub trait Solver: Sync {
+    /// Solve the puzzle for the given input, returning the answers to report
+    fn solve(&self, input: &str) -> Result<String>;
+}
+
+/// A solver registered for a (year, day) using `register_solver!`
+pub struct Registration {
+    pub year: u16,
+    pub day: u8,
+    pub solver: &'static dyn Solver,
+}
+
+inventory::collect!(Registration);
+
+/// Register a solver for a (year, day), so that it can be invoked using `registry::run`.
+///
+/// The solver must be a constant expression of a type implementing `Solver`, e.g. a unit
+/// struct:
+///
+/// ```ignore
+/// struct Day17;
+///
+/// impl aoc::registry::Solver for Day17 { ... }
+///
+/// aoc::register_solver!(2024, 17, Day17);
+/// ```
+///
+/// Registrations are only seen by binaries that link the crate containing them. A runner
+/// binary for solvers in a library target therefore needs to reference that library, e.g.
+/// using `use aoc2024 as _;`.
+#[macro_export]
+macro_rules! register_solver {
+    ($year:expr, $day:expr, $solver:expr) => {
+        $crate::registry::inventory::submit! {
+            $crate::registry::Registration {
+                year: $year,
+                day: $day,
+                solver: &$solver,
+            }
+        }
+    };
+}
+
+/// Get the solver registered for a (year, day).
+///
+/// Fails if no solver or more than one solver was registered for it.
+pub fn get(year: u16, day: u8) -> Result<&'static dyn Solver> {
+    let solvers: Vec<&'static dyn Solver> = inventory::iter::<Registration>
+        .into_iter()
+        .filter(|r| r.year == year && r.day == day)
+        .map(|r| r.solver)
+        .collect();
+
+    match solvers[..] {
+        [solver] => Ok(solver),
+        [] => bail!("No solver registered for {} day {}", year, day),
+        _ => bail!(
+            "{} solvers registered for {} day {}",
+            solvers.len(),
+            year,
+            day
+        ),
+    }
+}
+
+/// List all (year, day) pairs that have a registered solver, in order and without duplicates
+pub fn registered() -> Vec<(u16, u8)> {
+    let mut out: Vec<(u16, u8)> = inventory::iter::<Registration>
+        .into_iter()
+        .map(|r| (r.year, r.day))
+        .collect();
+    out.sort();
+    out.dedup();
+    out
+}
+
+/// Run the solver registered for a (year, day) on an input
+pub fn run(year: u16, day: u8, input: &str) -> Result<String> {
+    get(year, day)?.solve(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Dummy;
+
+    impl Solver for Dummy {
+        fn solve(&self, input: &str) -> Result<String> {
+            Ok(format!("{} lines", input.lines().count()))
+        }
+    }
+
+    crate::register_solver!(1999, 24, Dummy);
+    crate::register_solver!(1999, 26, Dummy);
+    crate::register_solver!(1999, 26, Dummy);
+
+    #[test]
+    fn test_registry() {
+        assert_eq!(run(1999, 24, "a\nb\nc\n").unwrap(), "3 lines");
+        assert!(registered().contains(&(1999, 24)));
+
+        assert!(get(1999, 25).is_err());
+        assert!(run(1999, 25, "").is_err());
+    }
+
+    #[test]
+    fn test_registry_duplicate() {
+        assert_eq!(
+            get(1999, 26).err().unwrap().to_string(),
+            "2 solvers registered for 1999 day 26"
+        );
+        assert!(run(1999, 26, "").is_err());
+
+        let days = registered();
+        assert_eq!(days.iter().filter(|d| **d == (1999, 26)).count(), 1);
+    }
+}
@@ -0,0 +1,412 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap, HashSet},
+    fmt::Display,
+    hash::Hash,
+};
+
+use crate::{
+    direction::Direction,
+    map::{IntCoord, Map, MapTile},
+};
+
+/// Render a line-by-line diff of two multi-line strings, prefixing lines only in `expected`
+/// with `-` and lines only in `actual` with `+`
+fn line_diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    let mut out = String::new();
+    for i in 0..expected.len().max(actual.len()) {
+        match (expected.get(i), actual.get(i)) {
+            (Some(e), Some(a)) if e == a => out.push_str(&format!("  {}\n", e)),
+            (e, a) => {
+                if let Some(e) = e {
+                    out.push_str(&format!("- {}\n", e));
+                }
+                if let Some(a) = a {
+                    out.push_str(&format!("+ {}\n", a));
+                }
+            }
+        }
+    }
+
+    out
+}
+
+/// Assert that `expected` is among the successors of `current`, returning the matching
+/// successor.
+///
+/// On failure, panics with a rendering of `current` and a diff of every generated successor
+/// against `expected`.
+pub fn expect_successor<S, F>(current: &S, expected: &S, successors: F) -> S
+where
+    S: PartialEq + Display,
+    F: FnOnce(&S) -> Vec<S>,
+{
+    expect_successor_by(current, expected, successors, |a, b| a == b)
+}
+
+/// Like `expect_successor`, but compare states using a custom predicate, e.g. to only compare
+/// positions and check other state fields separately.
+pub fn expect_successor_by<S, F, P>(current: &S, expected: &S, successors: F, same: P) -> S
+where
+    S: Display,
+    F: FnOnce(&S) -> Vec<S>,
+    P: Fn(&S, &S) -> bool,
+{
+    let candidates = successors(current);
+
+    let mut message = format!(
+        "Missing successor!\nCurrent state:\n{}\nExpected successor:\n{}\n",
+        current, expected
+    );
+
+    let expected_str = expected.to_string();
+    let mut found = None;
+    for (i, candidate) in candidates.into_iter().enumerate() {
+        if same(&candidate, expected) {
+            found = Some(candidate);
+            break;
+        }
+
+        message.push_str(&format!(
+            "Generated successor {} (diff to expected):\n{}\n",
+            i,
+            line_diff(&expected_str, &candidate.to_string())
+        ));
+    }
+
+    match found {
+        Some(s) => s,
+        None => panic!("{}", message),
+    }
+}
+
+/// A `BinaryHeap` entry that is ordered only by its score `K`, with the smallest score popped
+/// first. The payload `T` does not need to implement `Ord`.
+#[derive(Debug, Clone, Copy)]
+pub struct MinScored<K, T>(pub K, pub T);
+
+impl<K: Ord, T> PartialEq for MinScored<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K: Ord, T> Eq for MinScored<K, T> {}
+
+impl<K: Ord, T> PartialOrd for MinScored<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> Ord for MinScored<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+
+/// A `BinaryHeap` entry that is ordered only by its score `K`, with the largest score popped
+/// first. The payload `T` does not need to implement `Ord`.
+#[derive(Debug, Clone, Copy)]
+pub struct MaxScored<K, T>(pub K, pub T);
+
+impl<K: Ord, T> PartialEq for MaxScored<K, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<K: Ord, T> Eq for MaxScored<K, T> {}
+
+impl<K: Ord, T> PartialOrd for MaxScored<K, T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K: Ord, T> Ord for MaxScored<K, T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.cmp(&other.0)
+    }
+}
+
+/// Find the cost of the cheapest path from `start` to a state for which `is_goal` holds,
+/// using Dijkstra's algorithm over states whose `successors` are listed with their step costs
+pub fn dijkstra_states<S, F, G, It>(start: S, successors: F, is_goal: G) -> Option<usize>
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> It,
+    G: Fn(&S) -> bool,
+    It: IntoIterator<Item = (S, usize)>,
+{
+    dijkstra_states_path(start, successors, is_goal).map(|(cost, _)| cost)
+}
+
+/// Like `dijkstra_states`, but also returns the states along the cheapest path, starting with
+/// `start` and ending with the goal state
+pub fn dijkstra_states_path<S, F, G, It>(
+    start: S,
+    mut successors: F,
+    is_goal: G,
+) -> Option<(usize, Vec<S>)>
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> It,
+    G: Fn(&S) -> bool,
+    It: IntoIterator<Item = (S, usize)>,
+{
+    let mut costs: HashMap<S, usize> = HashMap::new();
+    let mut preds: HashMap<S, S> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+
+    costs.insert(start.clone(), 0);
+    queue.push(MinScored(0, start));
+
+    while let Some(MinScored(cost, state)) = queue.pop() {
+        if costs.get(&state).is_some_and(|c| *c < cost) {
+            continue;
+        }
+
+        if is_goal(&state) {
+            let mut path = vec![state];
+            while let Some(prev) = preds.get(path.last().expect("path is never empty")) {
+                path.push(prev.clone());
+            }
+            path.reverse();
+
+            return Some((cost, path));
+        }
+
+        for (next, step) in successors(&state) {
+            let next_cost = cost + step;
+            if costs.get(&next).is_some_and(|c| *c <= next_cost) {
+                continue;
+            }
+
+            costs.insert(next.clone(), next_cost);
+            preds.insert(next.clone(), state.clone());
+            queue.push(MinScored(next_cost, next));
+        }
+    }
+
+    None
+}
+
+/// Find the cheapest walk from `start`, facing `start_dir`, to `goal` on a grid where moving
+/// forward onto a `passable` tile costs `step_cost` and turning by 90 degrees in place costs
+/// `turn_cost`.
+///
+/// Returns the cheapest cost together with all coordinates that lie on any cheapest walk.
+pub fn grid_turn_cost<T, I, P>(
+    map: &Map<[I; 2], T>,
+    start: [I; 2],
+    start_dir: Direction,
+    goal: [I; 2],
+    step_cost: usize,
+    turn_cost: usize,
+    passable: P,
+) -> Option<(usize, HashSet<[I; 2]>)>
+where
+    T: MapTile,
+    I: IntCoord,
+    P: Fn(&T) -> bool,
+{
+    let is_passable = |pos: &[I; 2]| map.get(pos).is_some_and(&passable);
+
+    let mut costs: HashMap<([I; 2], Direction), usize> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    let mut best = None;
+
+    costs.insert((start, start_dir), 0);
+    queue.push(MinScored(0, (start, start_dir)));
+
+    while let Some(MinScored(cost, (pos, dir))) = queue.pop() {
+        if best.is_some_and(|b| cost > b) {
+            break;
+        }
+
+        if costs.get(&(pos, dir)).is_some_and(|c| *c < cost) {
+            continue;
+        }
+
+        if pos == goal {
+            best = Some(cost);
+            continue;
+        }
+
+        let mut successors = vec![
+            ((pos, dir.rot_left()), turn_cost),
+            ((pos, dir.rot_right()), turn_cost),
+        ];
+        if let Some(next) = dir.try_step(pos).filter(|next| is_passable(next)) {
+            successors.push(((next, dir), step_cost));
+        }
+
+        for (state, step) in successors {
+            let next_cost = cost + step;
+            if costs.get(&state).is_some_and(|c| *c <= next_cost) {
+                continue;
+            }
+
+            costs.insert(state, next_cost);
+            queue.push(MinScored(next_cost, state));
+        }
+    }
+
+    let best = best?;
+
+    // walk backwards from the goal along all transitions that are tight in cost
+    let mut stack: Vec<([I; 2], Direction)> = costs
+        .iter()
+        .filter(|((pos, _), cost)| *pos == goal && **cost == best)
+        .map(|(state, _)| *state)
+        .collect();
+    let mut seen: HashSet<([I; 2], Direction)> = stack.iter().copied().collect();
+
+    while let Some((pos, dir)) = stack.pop() {
+        let cost = costs[&(pos, dir)];
+
+        let mut predecessors = vec![
+            ((pos, dir.rot_left()), turn_cost),
+            ((pos, dir.rot_right()), turn_cost),
+        ];
+        if let Some(prev) = dir.reverse().try_step(pos) {
+            predecessors.push(((prev, dir), step_cost));
+        }
+
+        for (state, step) in predecessors {
+            if costs.get(&state).is_some_and(|c| c + step == cost) && seen.insert(state) {
+                stack.push(state);
+            }
+        }
+    }
+
+    Some((best, seen.into_iter().map(|(pos, _)| pos).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn successors(n: &u32) -> Vec<u32> {
+        vec![n + 1, n * 2]
+    }
+
+    #[test]
+    fn test_expect_successor() {
+        assert_eq!(expect_successor(&3, &6, successors), 6);
+        assert_eq!(
+            expect_successor_by(&3, &14, successors, |a, b| a % 10 == b % 10),
+            4
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Generated successor 1 (diff to expected):\n- 7\n+ 6\n")]
+    fn test_expect_successor_missing() {
+        expect_successor(&3, &7, successors);
+    }
+
+    #[test]
+    fn test_line_diff() {
+        assert_eq!(
+            line_diff("ab\ncd\n", "ab\nce\nf"),
+            "  ab\n- cd\n+ ce\n+ f\n"
+        );
+    }
+
+    #[test]
+    fn test_scored_heaps() {
+        use std::collections::BinaryHeap;
+
+        // payloads that are not Ord
+        let items = [
+            (3, vec![0.3]),
+            (1, vec![0.1]),
+            (4, vec![0.4]),
+            (2, vec![0.2]),
+        ];
+
+        let mut min_heap: BinaryHeap<_> = items.iter().map(|(k, v)| MinScored(*k, v)).collect();
+        let mut popped = Vec::new();
+        while let Some(MinScored(k, v)) = min_heap.pop() {
+            assert_eq!(v[0], k as f64 / 10.0);
+            popped.push(k);
+        }
+        assert_eq!(popped, vec![1, 2, 3, 4]);
+
+        let mut max_heap: BinaryHeap<_> = items.iter().map(|(k, v)| MaxScored(*k, v)).collect();
+        let popped: Vec<i32> = std::iter::from_fn(|| max_heap.pop().map(|s| s.0)).collect();
+        assert_eq!(popped, vec![4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn test_dijkstra_states() {
+        let edges: HashMap<char, Vec<(char, usize)>> = HashMap::from([
+            ('a', vec![('b', 7), ('c', 9), ('f', 14)]),
+            ('b', vec![('c', 10), ('d', 15)]),
+            ('c', vec![('d', 11), ('f', 2)]),
+            ('d', vec![('e', 6)]),
+            ('f', vec![('e', 9)]),
+        ]);
+        let successors = |s: &char| edges.get(s).cloned().unwrap_or_default();
+
+        let (cost, path) = dijkstra_states_path('a', successors, |s| *s == 'e').unwrap();
+        assert_eq!(cost, 20);
+        assert_eq!(path, vec!['a', 'c', 'f', 'e']);
+
+        let step_costs: usize = path
+            .windows(2)
+            .map(|w| {
+                successors(&w[0])
+                    .iter()
+                    .find(|(n, _)| *n == w[1])
+                    .unwrap()
+                    .1
+            })
+            .sum();
+        assert_eq!(step_costs, cost);
+
+        assert_eq!(dijkstra_states('a', successors, |s| *s == 'd'), Some(20));
+        assert_eq!(dijkstra_states('a', successors, |s| *s == 'a'), Some(0));
+        assert_eq!(dijkstra_states('e', successors, |s| *s == 'a'), None);
+    }
+
+    #[test]
+    fn test_grid_turn_cost() {
+        let examples = [
+            (
+                "###############\n#.......#....E#\n#.#.###.#.###.#\n#.....#.#...#.#\n#.###.#####.#.#\n#.#.#.......#.#\n#.#.#####.###.#\n#...........#.#\n###.#.#####.#.#\n#...#.....#.#.#\n#.#.#.###.#.#.#\n#.....#...#.#.#\n#.###.#.#.#.#.#\n#S..#.....#...#\n###############\n",
+                7036,
+                45,
+            ),
+            (
+                "#################\n#...#...#...#..E#\n#.#.#.#.#.#.#.#.#\n#.#.#.#...#...#.#\n#.#.#.#.###.#.#.#\n#...#.#.#.....#.#\n#.#.#.#.#.#####.#\n#.#...#.#.#.....#\n#.#.#####.#.###.#\n#.#.#.......#...#\n#.#.###.#####.###\n#.#.#...#.....#.#\n#.#.#.#####.###.#\n#.#.#.........#.#\n#.#.#.#########.#\n#S#.............#\n#################\n",
+                11048,
+                64,
+            ),
+        ];
+
+        for (input, score, tiles) in examples {
+            let map: Map<[i32; 2], char> = input.parse().unwrap();
+            let start = map.find_one_where(|_, t| *t == 'S').unwrap();
+            let goal = map.find_one_where(|_, t| *t == 'E').unwrap();
+
+            let (best, on_path) =
+                grid_turn_cost(&map, start, Direction::East, goal, 1, 1000, |t| *t != '#').unwrap();
+            assert_eq!(best, score);
+            assert_eq!(on_path.len(), tiles);
+            assert!(on_path.contains(&start) && on_path.contains(&goal));
+        }
+
+        let walled: Map<[i32; 2], char> = "S#E\n".parse().unwrap();
+        assert_eq!(
+            grid_turn_cost(&walled, [0, 0], Direction::East, [0, 2], 1, 1000, |t| *t
+                != '#'),
+            None
+        );
+    }
+}
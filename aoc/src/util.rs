@@ -0,0 +1,373 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    hash::Hash,
+    iter::FromIterator,
+};
+
+/// Apply `step` to `start` a total of `total` times, returning the final state.
+///
+/// All visited states are remembered so that once a state repeats, complete cycles are skipped
+/// and only the remainder of the steps has to be simulated.
+pub fn simulate_with_cycle<S, F>(start: S, mut step: F, total: u64) -> S
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    let mut seen: HashMap<S, u64> = HashMap::new();
+    let mut history: Vec<S> = Vec::new();
+    let mut state = start;
+
+    for i in 0..total {
+        if let Some(&first) = seen.get(&state) {
+            let period = i - first;
+            let remaining = (total - i) % period;
+            return history[(first + remaining) as usize].clone();
+        }
+
+        seen.insert(state.clone(), i);
+        history.push(state.clone());
+
+        state = step(&state);
+    }
+
+    state
+}
+
+/// Count the ways to fill in the unknown (`None`) cells of `s` so that the runs of `true`
+/// cells have exactly the lengths in `groups`, in order
+pub fn count_matches(s: &[Option<bool>], groups: &[usize]) -> u64 {
+    fn count(
+        s: &[Option<bool>],
+        groups: &[usize],
+        i: usize,
+        g: usize,
+        memo: &mut HashMap<(usize, usize), u64>,
+    ) -> u64 {
+        if i >= s.len() {
+            return (g == groups.len()) as u64;
+        }
+
+        if let Some(&n) = memo.get(&(i, g)) {
+            return n;
+        }
+
+        let mut ways = 0;
+
+        // leave the cell empty
+        if s[i] != Some(true) {
+            ways += count(s, groups, i + 1, g, memo);
+        }
+
+        // start the next run here, followed by an empty cell or the end
+        if let Some(&n) = groups.get(g) {
+            let end = i + n;
+            let fits = end <= s.len()
+                && s[i..end].iter().all(|c| *c != Some(false))
+                && s.get(end) != Some(&Some(true));
+
+            if fits {
+                ways += count(s, groups, end + 1, g + 1, memo);
+            }
+        }
+
+        memo.insert((i, g), ways);
+        ways
+    }
+
+    count(s, groups, 0, 0, &mut HashMap::new())
+}
+
+/// Get the `k` largest items in descending order, keeping at most `k` items in memory
+pub fn top_k<T: Ord>(iter: impl Iterator<Item = T>, k: usize) -> Vec<T> {
+    let mut heap: BinaryHeap<Reverse<T>> = BinaryHeap::with_capacity(k + 1);
+
+    for item in iter {
+        if heap.len() < k {
+            heap.push(Reverse(item));
+        } else if heap.peek().is_some_and(|Reverse(min)| item > *min) {
+            heap.pop();
+            heap.push(Reverse(item));
+        }
+    }
+
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(item)| item)
+        .collect()
+}
+
+/// A set of small integers in `0..64`, stored as the bits of a `u64`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct BitMask(pub u64);
+
+impl BitMask {
+    pub fn new() -> Self {
+        BitMask(0)
+    }
+
+    /// Get the bit for an item, panicking for items outside of `0..64`
+    fn bit(i: usize) -> u64 {
+        assert!(i < 64, "BitMask item {} out of range 0..64", i);
+        1 << i
+    }
+
+    pub fn set(&mut self, i: usize) {
+        self.0 |= Self::bit(i);
+    }
+
+    pub fn clear(&mut self, i: usize) {
+        self.0 &= !Self::bit(i);
+    }
+
+    pub fn contains(&self, i: usize) -> bool {
+        self.0 & Self::bit(i) != 0
+    }
+
+    /// Get the number of items in the set
+    pub fn count(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(&self, other: &BitMask) -> BitMask {
+        BitMask(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: &BitMask) -> BitMask {
+        BitMask(self.0 & other.0)
+    }
+
+    /// Iterate over the items in the set in ascending order
+    pub fn iter_set_bits(&self) -> impl Iterator<Item = usize> {
+        let mut bits = self.0;
+        std::iter::from_fn(move || {
+            if bits == 0 {
+                return None;
+            }
+
+            let i = bits.trailing_zeros() as usize;
+            bits &= bits - 1;
+            Some(i)
+        })
+    }
+}
+
+impl FromIterator<usize> for BitMask {
+    fn from_iter<It: IntoIterator<Item = usize>>(iter: It) -> Self {
+        let mut mask = BitMask::new();
+        for i in iter {
+            mask.set(i);
+        }
+        mask
+    }
+}
+
+/// Count how often each distinct item occurs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter<T: Hash + Eq> {
+    counts: HashMap<T, usize>,
+}
+
+impl<T: Hash + Eq> Default for Counter<T> {
+    fn default() -> Self {
+        Counter {
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl<T: Hash + Eq> Counter<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count one more occurrence of `item`
+    pub fn add(&mut self, item: T) {
+        *self.counts.entry(item).or_insert(0) += 1;
+    }
+
+    /// Get the number of occurrences of `item`, which is 0 for items never added
+    pub fn get(&self, item: &T) -> usize {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    /// Get the total number of occurrences of all items
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Iterate over all distinct items and their counts in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&T, usize)> {
+        self.counts.iter().map(|(k, v)| (k, *v))
+    }
+
+    /// Get the `n` most frequent items with their counts, most frequent first. Items with the
+    /// same count are ordered from smallest to largest.
+    pub fn most_common(&self, n: usize) -> Vec<(&T, usize)>
+    where
+        T: Ord,
+    {
+        let mut out: Vec<(&T, usize)> = self.iter().collect();
+        out.sort_by(|(a, na), (b, nb)| nb.cmp(na).then_with(|| a.cmp(b)));
+        out.truncate(n);
+        out
+    }
+}
+
+impl<T: Hash + Eq> FromIterator<T> for Counter<T> {
+    fn from_iter<It: IntoIterator<Item = T>>(iter: It) -> Self {
+        let mut counter = Counter::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+impl<T: Hash + Eq> Extend<T> for Counter<T> {
+    fn extend<It: IntoIterator<Item = T>>(&mut self, iter: It) {
+        for item in iter {
+            self.add(item);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_simulate_with_cycle() {
+        // a tail of 3 states leading into a cycle of length 7
+        let step = |s: &u64| if *s < 3 { s + 1 } else { 3 + (s - 3 + 1) % 7 };
+
+        for total in 0..50 {
+            let mut naive = 0;
+            for _ in 0..total {
+                naive = step(&naive);
+            }
+
+            assert_eq!(simulate_with_cycle(0, step, total), naive);
+        }
+
+        let mut calls = 0;
+        let state = simulate_with_cycle(
+            0,
+            |s| {
+                calls += 1;
+                step(s)
+            },
+            1_000_000_000_000,
+        );
+
+        assert_eq!(state, 3 + (1_000_000_000_000 - 3) % 7);
+        assert!(calls <= 10);
+    }
+
+    #[test]
+    fn test_counter() {
+        let counter: Counter<char> = "abracadabra".chars().collect();
+
+        assert_eq!(counter.get(&'a'), 5);
+        assert_eq!(counter.get(&'z'), 0);
+        assert_eq!(counter.total(), 11);
+
+        // 'b' and 'r' are tied and ordered alphabetically
+        assert_eq!(
+            counter.most_common(3),
+            vec![(&'a', 5), (&'b', 2), (&'r', 2)]
+        );
+        assert_eq!(counter.most_common(100).len(), 5);
+        assert_eq!(counter.most_common(100).last(), Some(&(&'d', 1)));
+
+        let mut counter = Counter::new();
+        counter.add(3);
+        counter.extend(vec![4, 3]);
+        assert_eq!(counter.most_common(1), vec![(&3, 2)]);
+    }
+
+    #[test]
+    fn test_top_k() {
+        assert_eq!(top_k(vec![1, 5, 3, 2, 4].into_iter(), 3), vec![5, 4, 3]);
+        assert_eq!(top_k(vec![1, 5, 5, 2].into_iter(), 2), vec![5, 5]);
+        assert_eq!(top_k(vec![2, 1].into_iter(), 5), vec![2, 1]);
+        assert_eq!(top_k(vec![2, 1].into_iter(), 0), Vec::<i32>::new());
+    }
+
+    #[test]
+    fn test_bitmask() {
+        let mut a = BitMask::new();
+        assert!(a.is_empty());
+
+        a.set(0);
+        a.set(5);
+        a.set(63);
+        a.set(5);
+        assert_eq!(a.count(), 3);
+        assert!(a.contains(5));
+        assert!(!a.contains(4));
+        assert_eq!(a.iter_set_bits().collect::<Vec<_>>(), vec![0, 5, 63]);
+
+        a.clear(0);
+        a.clear(1);
+        assert_eq!(a.iter_set_bits().collect::<Vec<_>>(), vec![5, 63]);
+
+        let b: BitMask = vec![1, 5, 7].into_iter().collect();
+        assert_eq!(
+            a.union(&b).iter_set_bits().collect::<Vec<_>>(),
+            vec![1, 5, 7, 63]
+        );
+        assert_eq!(a.intersection(&b), BitMask(1 << 5));
+        assert!(a.intersection(&BitMask(0b10)).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "BitMask item 64 out of range 0..64")]
+    fn test_bitmask_set_out_of_range() {
+        BitMask::new().set(64);
+    }
+
+    #[test]
+    #[should_panic(expected = "BitMask item 64 out of range 0..64")]
+    fn test_bitmask_contains_out_of_range() {
+        BitMask(1).contains(64);
+    }
+
+    #[test]
+    fn test_count_matches() {
+        let parse = |s: &str| -> Vec<Option<bool>> {
+            s.chars()
+                .map(|c| match c {
+                    '#' => Some(true),
+                    '.' => Some(false),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        let examples: [(&str, &[usize], u64); 6] = [
+            ("???.###", &[1, 1, 3], 1),
+            (".??..??...?##.", &[1, 1, 3], 4),
+            ("?#?#?#?#?#?#?#?", &[1, 3, 1, 6], 1),
+            ("????.#...#...", &[4, 1, 1], 1),
+            ("????.######..#####.", &[1, 6, 5], 4),
+            ("?###????????", &[3, 2, 1], 10),
+        ];
+
+        for (s, groups, expected) in examples.iter() {
+            assert_eq!(count_matches(&parse(s), groups), *expected, "{}", s);
+        }
+
+        // five-fold unfolded version of the last row
+        let unfolded = ["?###????????"; 5].join("?");
+        assert_eq!(
+            count_matches(&parse(&unfolded), &[3, 2, 1].repeat(5)),
+            506250
+        );
+
+        assert_eq!(count_matches(&[], &[]), 1);
+        assert_eq!(count_matches(&parse("#"), &[]), 0);
+    }
+}
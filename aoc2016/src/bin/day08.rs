@@ -1,14 +1,5 @@
 use anyhow::{anyhow, Context, Result};
-use aoc::map::Map;
-
-#[derive(Debug, Clone)]
-struct Tile;
-
-impl std::fmt::Display for Tile {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "#")
-    }
-}
+use aoc::grid::Screen;
 
 #[derive(Debug)]
 enum Instruction {
@@ -62,57 +53,11 @@ const WIDTH: usize = 50;
 const HEIGHT: usize = 6;
 
 impl Instruction {
-    fn apply_to(&self, map: &mut Map<[usize; 2], Tile>) {
+    fn apply_to(&self, screen: &mut Screen) {
         match self {
-            Instruction::Rect { width, height } => {
-                for i in 0..*height {
-                    for j in 0..*width {
-                        map.set([i, j], Tile);
-                    }
-                }
-            }
-            Instruction::RotateColumn { x, by } => {
-                for _ in 0..*by {
-                    // save the rightmost pixel
-                    let save = map.get(&[HEIGHT - 1, *x]).is_some();
-
-                    for k in 1..HEIGHT {
-                        let i = HEIGHT - k;
-                        if map.get(&[i - 1, *x]).is_some() {
-                            map.set([i, *x], Tile);
-                        } else {
-                            map.remove(&[i, *x]);
-                        }
-                    }
-
-                    if save {
-                        map.set([0, *x], Tile);
-                    } else {
-                        map.remove(&[0, *x]);
-                    }
-                }
-            }
-            Instruction::RotateRow { y, by } => {
-                for _ in 0..*by {
-                    // save the rightmost pixel
-                    let save = map.get(&[*y, WIDTH - 1]).is_some();
-
-                    for k in 1..WIDTH {
-                        let j = WIDTH - k;
-                        if map.get(&[*y, j - 1]).is_some() {
-                            map.set([*y, j], Tile);
-                        } else {
-                            map.remove(&[*y, j]);
-                        }
-                    }
-
-                    if save {
-                        map.set([*y, 0], Tile);
-                    } else {
-                        map.remove(&[*y, 0]);
-                    }
-                }
-            }
+            Instruction::Rect { width, height } => screen.rect(*width, *height),
+            Instruction::RotateColumn { x, by } => screen.rotate_col(*x, *by),
+            Instruction::RotateRow { y, by } => screen.rotate_row(*y, *by),
         }
     }
 }
@@ -120,8 +65,7 @@ impl Instruction {
 fn main() -> Result<()> {
     let instructions: Vec<Instruction> = aoc::io::read_lines("data/day08/input")?;
 
-    let mut state: Map<[usize; 2], Tile> = Map::new();
-    state.fixed_extent = Some(([0, 0], [HEIGHT, WIDTH]));
+    let mut state = Screen::new(WIDTH, HEIGHT);
 
     for inst in instructions {
         inst.apply_to(&mut state);
@@ -129,7 +73,7 @@ fn main() -> Result<()> {
         println!("{:?}\n{}", inst, state);
     }
 
-    let n_on = state.data.len();
+    let n_on = state.count_lit();
 
     println!("Part 1: got {} pixels that are lit", n_on);
     println!("Part 2:\n{}", state);
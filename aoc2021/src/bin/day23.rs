@@ -410,20 +410,14 @@ mod test {
             let prev = &states[i];
             let next = &states[i + 1];
 
-            let steps = prev.get_steps(2);
-            if let Some(step) = steps.iter().find(|step| step.positions == next.positions) {
-                assert_eq!(step.energy, next.energy);
-            } else {
-                println!("In frame {}, missing this step:\n{}", i, next);
-
-                println!("Generated:");
-
-                for step in steps {
-                    println!("{}", step);
-                }
-
-                panic!("Missing step");
-            }
+            let step = aoc::search::expect_successor_by(
+                prev,
+                next,
+                |s| s.get_steps(2),
+                |a, b| a.positions == b.positions,
+            );
+
+            assert_eq!(step.energy, next.energy);
         }
 
         Ok(())
@@ -1,11 +1,9 @@
 use std::{collections::HashMap, fs::File, io::Read};
 
-use anyhow::{anyhow, bail, Context, Result};
+use anyhow::{anyhow, bail, Result};
 use colored::Colorize;
-use lazy_static::lazy_static;
-use regex::Regex;
 
-use aoc::direction::Direction;
+use aoc::direction::{Direction, TurnWalk};
 
 type Pos = [i32; 2];
 type Map = aoc::map::Map<Pos, Tile>;
@@ -37,35 +35,10 @@ impl aoc::map::ParseMapTile for Tile {
     }
 }
 
-#[derive(Debug, Clone)]
-enum Step {
-    Step(usize),
-    Right,
-    Left,
-}
-
-lazy_static! {
-    static ref RE_PATH: Regex = Regex::new(r"(\d+|L|R)").unwrap();
-}
-
-impl Step {
-    fn parse_steps(s: &str) -> Result<Vec<Step>> {
-        RE_PATH
-            .find_iter(s)
-            .map(|c| c.as_str())
-            .map(|s| match s {
-                "L" => Ok(Step::Left),
-                "R" => Ok(Step::Right),
-                _ => Ok(Step::Step(s.parse().context("Step count")?)),
-            })
-            .collect::<Result<Vec<Step>>>()
-    }
-}
-
 #[derive(Debug, Clone)]
 struct State {
     map: Map,
-    path: Vec<Step>,
+    path: Vec<TurnWalk>,
     i_path: usize,
 
     location: Pos,
@@ -95,13 +68,8 @@ impl State {
         let mut buf = String::new();
         File::open(path)?.read_to_string(&mut buf)?;
 
-        let lines = buf
-            .lines()
-            .filter(|l| !l.trim().is_empty())
-            .collect::<Vec<_>>();
-
-        let path: Vec<Step> = Step::parse_steps(lines.last().expect("Last line"))?;
-        let map: Map = lines[..lines.len() - 1].join("\n").parse()?;
+        let (map, tail): (Map, String) = aoc::io::read_map_and_tail(&buf)?;
+        let path: Vec<TurnWalk> = TurnWalk::parse(&tail)?;
 
         let location = *map
             .data
@@ -382,7 +350,7 @@ impl State {
         self.i_path += 1;
 
         match next_step {
-            Step::Step(n) => {
+            TurnWalk::Walk(n) => {
                 // walk forward
                 let [mut i, mut j] = self.location;
 
@@ -417,8 +385,8 @@ impl State {
 
                 self.location = [i, j];
             }
-            Step::Right => self.direction = self.direction.rot_right(),
-            Step::Left => self.direction = self.direction.rot_left(),
+            TurnWalk::Right => self.direction = self.direction.rot_right(),
+            TurnWalk::Left => self.direction = self.direction.rot_left(),
         }
     }
 
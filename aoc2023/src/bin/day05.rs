@@ -149,58 +149,28 @@ impl Map {
     }
 
     fn transform_range(&self, (source_start, source_end): (usize, usize)) -> Vec<(usize, usize)> {
-        // 01234567890123456789
-        //amen und Herren,
-        //     ssssssssss
-        // xx aaa bb  cccccc
-        //     AABCCDDEEE
-
-        let mut out = Vec::new();
-
-        // ii is a special index!
-        // even numbers correspond to the "empty" space before entry ii/2
-        // odd numbers correspond to the entry (ii-1)/2
-        for ii in 0..=(self.entries.len() * 2) {
-            if ii % 2 == 0 {
-                // we are in an empty space
-                let (start, end) = if ii == 0 {
-                    // first empty space
-                    if self.entries[0].source_start == 0 {
-                        continue;
-                    }
-                    (0, self.entries[0].source_start - 1)
-                } else if ii >= self.entries.len() * 2 {
-                    // last empty space after all entries
-                    let prev = &self.entries[ii / 2 - 1];
-                    (prev.source_start + prev.length, usize::MAX)
-                } else {
-                    // empty space between entries
-                    let prev = &self.entries[ii / 2 - 1];
-                    let next = &self.entries[ii / 2];
-                    (prev.source_start + prev.length, next.source_start - 1)
-                };
-
-                let overlap_start = usize::max(start, source_start);
-                let overlap_end = usize::min(end, source_end);
-
-                if overlap_end >= overlap_start {
-                    out.push((overlap_start, overlap_end));
-                }
-            } else {
-                // we are in an entry
-                let entry = &self.entries[(ii - 1) / 2];
-
-                let overlap_start = usize::max(entry.source_start, source_start);
-                let overlap_end = usize::min(entry.source_start + entry.length - 1, source_end);
-
-                if overlap_end >= overlap_start {
-                    out.push((
-                        overlap_start - entry.source_start + entry.destination_start,
-                        overlap_end - entry.source_start + entry.destination_start,
-                    ));
-                }
-            }
-        }
+        let cuts: Vec<(i64, i64)> = self
+            .entries
+            .iter()
+            .map(|e| {
+                (
+                    e.source_start as i64,
+                    (e.source_start + e.length) as i64 - 1,
+                )
+            })
+            .collect();
+
+        let out: Vec<(usize, usize)> =
+            aoc::math::split_ranges(&[(source_start as i64, source_end as i64)], &cuts)
+                .into_iter()
+                .map(|(start, end, cut)| match cut {
+                    Some(i) => (
+                        self.entries[i].transform(start as usize),
+                        self.entries[i].transform(end as usize),
+                    ),
+                    None => (start as usize, end as usize),
+                })
+                .collect();
 
         let out_len: usize = out.iter().map(|(s, e)| e - s + 1).sum();
 
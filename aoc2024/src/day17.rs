@@ -0,0 +1,37 @@
+use anyhow::Result;
+use itertools::Itertools;
+
+use aoc::chronospatial::Bitcode;
+use aoc::registry::Solver;
+
+pub struct Day17;
+
+impl Solver for Day17 {
+    fn solve(&self, input: &str) -> Result<String> {
+        let prog: Bitcode = input.parse()?;
+
+        let outputs = prog.clone().run()?;
+        let quine_input = prog.find_quine()?;
+
+        Ok(format!(
+            "Part 1: {}\nPart 2: {}",
+            outputs.iter().map(|v| format!("{}", v)).join(","),
+            quine_input
+        ))
+    }
+}
+
+aoc::register_solver!(2024, 17, Day17);
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn test_registered() {
+        let input = "Register A: 2024\nRegister B: 0\nRegister C: 0\n\nProgram: 0,3,5,4,3,0\n";
+
+        assert_eq!(
+            aoc::registry::run(2024, 17, input).unwrap(),
+            "Part 1: 5,7,3,0\nPart 2: 117440"
+        );
+    }
+}
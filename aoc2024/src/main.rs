@@ -0,0 +1,28 @@
+use anyhow::{bail, Context, Result};
+
+// link the solvers that register themselves with `aoc::registry`
+use aoc2024 as _;
+
+fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    let [year, day] = &args[..] else {
+        let days = aoc::registry::registered()
+            .iter()
+            .map(|(year, day)| format!("{} {}", year, day))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        bail!("Usage: aoc2024 <year> <day> (available: {})", days);
+    };
+
+    let year: u16 = year.parse().context("Parse year")?;
+    let day: u8 = day.parse().context("Parse day")?;
+
+    let solver = aoc::registry::get(year, day)?;
+
+    let input = aoc::io::read_all((year, day))?;
+    println!("{}", solver.solve(&input)?);
+
+    Ok(())
+}